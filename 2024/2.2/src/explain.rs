@@ -0,0 +1,81 @@
+use std::fmt;
+
+use crate::policy::{SafetyPolicy, Violation};
+
+/// Why a report is unsafe and which single levels could be removed to fix it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub violation: Violation,
+    pub removable: Vec<usize>,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsafe, {}", self.violation)?;
+        if self.removable.is_empty() {
+            return write!(f, ", no single removal fixes it");
+        }
+
+        let indices: Vec<_> = self.removable.iter().map(|i| i.to_string()).collect();
+        write!(f, ", fixed by removing index {}", indices.join(" or "))
+    }
+}
+
+/// Explains why a report is unsafe under the given policy, or returns `None`
+/// if it is safe as is.
+pub fn explain(policy: &SafetyPolicy, levels: &[u32]) -> Option<Verdict> {
+    let violation = policy.find_violation(levels)?;
+
+    let removable = (0..levels.len())
+        .filter(|&idx| {
+            let mut candidate = levels.to_vec();
+            candidate.remove(idx);
+            policy.find_violation(&candidate).is_none()
+        })
+        .collect();
+
+    Some(Verdict {
+        violation,
+        removable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain() {
+        let policy = SafetyPolicy::part2();
+        assert_eq!(explain(&policy, &[7, 6, 4, 2, 1]), None);
+        assert_eq!(
+            explain(&policy, &[1, 2, 7, 8, 9]),
+            Some(Verdict {
+                violation: Violation::StepOutOfRange { index: 1, diff: 5 },
+                removable: vec![],
+            })
+        );
+        assert_eq!(
+            explain(&policy, &[1, 3, 2, 4, 5]),
+            Some(Verdict {
+                violation: Violation::NotMonotone { index: 1 },
+                removable: vec![1, 2],
+            })
+        );
+        assert_eq!(
+            explain(&policy, &[8, 6, 4, 4, 1]),
+            Some(Verdict {
+                violation: Violation::StepOutOfRange { index: 2, diff: 0 },
+                removable: vec![2, 3],
+            })
+        );
+        // Removing the first level changes the direction of the whole report
+        assert_eq!(
+            explain(&policy, &[5, 1, 2, 3]),
+            Some(Verdict {
+                violation: Violation::StepOutOfRange { index: 0, diff: 4 },
+                removable: vec![0],
+            })
+        );
+    }
+}
@@ -1,50 +1,26 @@
+mod explain;
 mod input;
-use anyhow::Result;
-
-fn check_sortedness_w_removal<F>(levels: &[u32], idx: usize, cmp: F) -> Option<Vec<Vec<u32>>>
-where
-    F: Fn(&u32, &u32) -> bool,
-    F: Copy,
-{
-    let mut vec1 = levels.to_vec();
-    vec1.remove(idx);
-    let vec1 = vec1.is_sorted_by(cmp).then_some(vec1);
-
-    let mut vec2 = levels.to_vec();
-    vec2.remove(idx + 1);
-    let vec2 = vec2.is_sorted_by(cmp).then_some(vec2);
-
-    match (vec1, vec2) {
-        (Some(vec1), Some(vec2)) => Some(vec![vec1, vec2]),
-        (None, Some(vec2)) => Some(vec![vec2]),
-        (Some(vec1), None) => Some(vec![vec1]),
-        _ => None,
-    }
-}
+mod policy;
+mod stream;
 
-fn perform_check<F>(levels: &[u32], cmp: F) -> Option<Vec<Vec<u32>>>
-where
-    F: Fn(&u32, &u32) -> bool,
-    F: Copy,
-{
-    match levels.windows(2).position(|s| !cmp(&s[0], &s[1])) {
-        Some(idx) => check_sortedness_w_removal(levels, idx, cmp),
-        None => Some(vec![]),
-    }
-}
+use std::{io, num::ParseIntError};
 
-fn check_sortedness(levels: &[u32]) -> Option<Vec<Vec<u32>>> {
-    // Check if the list is sorted in ascending order
-    let cmp = |a: &u32, b: &u32| a < b;
-    let is_sorted_asc = perform_check(levels, cmp);
+use anyhow::{anyhow, bail, Result};
+use policy::SafetyPolicy;
 
-    if is_sorted_asc.is_some() {
-        return is_sorted_asc;
+/// Parses the levels of a report into `levels`, reusing its allocation.
+fn parse_levels_into(line: &str, levels: &mut Vec<u32>) -> Result<(), ParseIntError> {
+    levels.clear();
+    for num in line.split_ascii_whitespace() {
+        levels.push(num.parse()?);
     }
+    Ok(())
+}
 
-    // Check if the list is sorted in descending order
-    let cmp = |a: &u32, b: &u32| a > b;
-    perform_check(levels, cmp)
+fn parse_levels(line: &str) -> Result<Vec<u32>, ParseIntError> {
+    let mut levels = Vec::new();
+    parse_levels_into(line, &mut levels)?;
+    Ok(levels)
 }
 
 fn has_correct_diffs(levels: &[u32]) -> bool {
@@ -53,48 +29,95 @@ fn has_correct_diffs(levels: &[u32]) -> bool {
     levels.windows(2).all(diff_is_correct)
 }
 
-fn has_correct_diffs_w_removal(levels: &[u32]) -> bool {
-    let diff_is_correct = |s: &[u32]| (1..=3).contains(&s[0].abs_diff(s[1]));
+fn is_safe_without_removal(levels: &[u32]) -> bool {
+    (levels.is_sorted_by(|a, b| a < b) || levels.is_sorted_by(|a, b| a > b))
+        && has_correct_diffs(levels)
+}
 
-    levels
-        .windows(2)
-        .position(|s| !diff_is_correct(s))
-        .filter(|&idx| {
-            // Check if removing any element will make the list safe
+/// Reference implementation which tries removing every single level.
+fn is_safe_exhaustive(levels: &[u32]) -> bool {
+    is_safe_without_removal(levels)
+        || (0..levels.len()).any(|idx| {
             let mut vec = levels.to_vec();
             vec.remove(idx);
-            if vec.windows(2).all(diff_is_correct) {
-                return false;
-            }
-
-            let mut vec = levels.to_vec();
-            vec.remove(idx + 1);
-            !vec.windows(2).all(diff_is_correct)
+            is_safe_without_removal(&vec)
         })
-        .is_none()
 }
 
-fn is_safe(levels: &[u32]) -> bool {
-    let sorted = check_sortedness(levels);
-
-    match sorted {
-        Some(v) if v.is_empty() => has_correct_diffs_w_removal(levels),
-        Some(v) => v.iter().map(|v| has_correct_diffs(v)).any(|b| b),
-        None => false,
+fn explain_reports(input: &str, policy: &SafetyPolicy) {
+    for (nr, l) in input.lines().enumerate() {
+        let levels = parse_levels(l).unwrap();
+        match explain::explain(policy, &levels) {
+            Some(verdict) => println!("{}: {}: {}", nr + 1, l, verdict),
+            None => println!("{}: {}: safe", nr + 1, l),
+        }
     }
 }
 
 fn main() -> Result<()> {
+    let mut explain = false;
+    let mut stream = false;
+    let mut exhaustive = false;
+    let mut policy = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => explain = true,
+            "--exhaustive" => exhaustive = true,
+            "--stream" => stream = true,
+            "--policy" => {
+                let path = args.next().ok_or(anyhow!("--policy expects a file path"))?;
+                policy = Some(SafetyPolicy::from_file(path)?);
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
+
+    if stream && explain {
+        bail!("--stream and --explain can't be combined");
+    }
+    if exhaustive && (stream || explain) {
+        bail!("--exhaustive only applies to counting the safe reports of the input");
+    }
+    if exhaustive && policy.is_some() {
+        bail!("--exhaustive only checks the rules of part 2, it can't be used with --policy");
+    }
+    let is_default = policy.is_none();
+    let policy = policy.unwrap_or_else(SafetyPolicy::part2);
+
+    if stream {
+        // Line buffered, so the running counts show up while the input is
+        // still coming in
+        let summary = stream::run(
+            io::stdin().lock(),
+            io::LineWriter::new(io::stdout().lock()),
+            &policy,
+        )?;
+        print!("{}", summary);
+        return Ok(());
+    }
+
+    if explain {
+        explain_reports(input::INPUT, &policy);
+        return Ok(());
+    }
+
     let res = input::INPUT
         .lines()
         .map(|l| {
-            let levels: Vec<u32> = l.split(' ').map(|num| num.parse().unwrap()).collect();
-            is_safe(&levels)
+            let levels = parse_levels(l).unwrap();
+            if exhaustive {
+                is_safe_exhaustive(&levels)
+            } else {
+                policy.is_safe(&levels)
+            }
         })
         .filter(|&b| b)
         .count();
 
-    assert_eq!(res, 536);
+    if is_default {
+        assert_eq!(res, 536);
+    }
 
     println!("{}", res);
 
@@ -105,37 +128,6 @@ fn main() -> Result<()> {
 mod test {
     use super::*;
 
-    #[test]
-    fn test_check_sortednes_with_removal() {
-        assert_eq!(
-            check_sortedness_w_removal(&[1, 2, 3], 0, |a, b| a < b),
-            Some(vec![vec![2, 3], vec![1, 3]])
-        );
-        assert_eq!(
-            check_sortedness_w_removal(&[3, 2, 1], 0, |a, b| a < b),
-            None
-        );
-        assert_eq!(
-            check_sortedness_w_removal(&[2, 3, 1, 4], 1, |a, b| a < b),
-            Some(vec![vec![2, 3, 4]])
-        );
-        assert_eq!(
-            check_sortedness_w_removal(&[1, 3, 2], 1, |a, b| a < b),
-            Some(vec![vec![1, 2], vec![1, 3]])
-        );
-    }
-
-    #[test]
-    fn test_is_sorted() {
-        assert_eq!(check_sortedness(&[1, 2, 3]), Some(vec![]));
-        assert_eq!(check_sortedness(&[3, 2, 1]), Some(vec![]));
-        assert_eq!(check_sortedness(&[2, 3, 1, 4]), Some(vec![vec![2, 3, 4]]));
-        assert_eq!(
-            check_sortedness(&[1, 3, 2]),
-            Some(vec![vec![1, 2], vec![1, 3]])
-        );
-    }
-
     #[test]
     fn test_has_correct_diffs() {
         assert!(has_correct_diffs(&[1, 2, 3]));
@@ -147,6 +139,9 @@ mod test {
 
     #[test]
     fn test_is_safe() {
+        let policy = SafetyPolicy::part2();
+        let is_safe = |levels: &[u32]| policy.is_safe(levels);
+
         assert!(is_safe(&[1, 2, 3]));
         assert!(is_safe(&[1, 3, 5]));
         assert!(is_safe(&[3, 2, 1]));
@@ -159,5 +154,9 @@ mod test {
         assert!(!is_safe(&[1, 2, 7, 8, 9]));
         assert!(!is_safe(&[9, 7, 6, 2, 1]));
         assert!(!is_safe(&[1, 7, 1]));
+        // Removing the first or last level flips the direction
+        assert!(is_safe(&[0, 5, 4]));
+        assert!(is_safe(&[1, 0, 5]));
+        assert!(is_safe(&[5, 0, 4]));
     }
 }
@@ -0,0 +1,393 @@
+use std::{fmt, fs, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+
+/// The safety rule a report breaks first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// The step between `index` and `index + 1` doesn't follow the direction
+    /// of the report.
+    NotMonotone { index: usize },
+    /// The step between `index` and `index + 1` is outside of the allowed range.
+    StepOutOfRange { index: usize, diff: u32 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotMonotone { index } => {
+                write!(f, "not monotone between index {} and {}", index, index + 1)
+            }
+            Self::StepOutOfRange { index, diff } => write!(
+                f,
+                "step of {} between index {} and {} is out of range",
+                diff,
+                index,
+                index + 1
+            ),
+        }
+    }
+}
+
+/// The directions a report is allowed to go in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+    Either,
+}
+
+impl Direction {
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ascending" => Ok(Self::Ascending),
+            "descending" => Ok(Self::Descending),
+            "either" => Ok(Self::Either),
+            _ => Err(anyhow!("Invalid direction '{}'", s)),
+        }
+    }
+}
+
+/// The rules a report has to follow to be considered safe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyPolicy {
+    pub min_step: u32,
+    pub max_step: u32,
+    /// Whether two equal neighbouring levels break the monotonicity.
+    pub strict: bool,
+    pub direction: Direction,
+    /// How many levels may be removed to make a report safe.
+    pub tolerated_removals: usize,
+}
+
+impl SafetyPolicy {
+    /// The rules of part 1.
+    pub fn part1() -> Self {
+        Self {
+            min_step: 1,
+            max_step: 3,
+            strict: true,
+            direction: Direction::Either,
+            tolerated_removals: 0,
+        }
+    }
+
+    /// The rules of part 2, i.e. part 1 with the problem dampener.
+    pub fn part2() -> Self {
+        Self {
+            tolerated_removals: 1,
+            ..Self::part1()
+        }
+    }
+
+    /// Parses a policy from `key = value` lines.
+    ///
+    /// Keys that aren't given keep their value from [`SafetyPolicy::part1`].
+    /// Empty lines and everything after a `#` are ignored.
+    pub fn from_str(s: &str) -> Result<Self> {
+        let mut policy = Self::part1();
+
+        for (nr, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(anyhow!("Line {}: expected 'key = value'", nr + 1))?;
+            let value = value.trim();
+
+            match key.trim() {
+                "min_step" => policy.min_step = value.parse()?,
+                "max_step" => policy.max_step = value.parse()?,
+                "strict" => policy.strict = value.parse()?,
+                "direction" => policy.direction = Direction::from_str(value)?,
+                "tolerated_removals" => policy.tolerated_removals = value.parse()?,
+                key => bail!("Line {}: unknown key '{}'", nr + 1, key),
+            }
+        }
+
+        if policy.min_step > policy.max_step {
+            bail!(
+                "min_step {} is larger than max_step {}",
+                policy.min_step,
+                policy.max_step
+            );
+        }
+
+        Ok(policy)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_str(&fs::read_to_string(path)?)
+    }
+
+    /// Finds the first rule the levels break, without removing any of them.
+    pub fn find_violation(&self, levels: &[u32]) -> Option<Violation> {
+        let ascending = match self.direction {
+            Direction::Ascending => true,
+            Direction::Descending => false,
+            Direction::Either => levels
+                .windows(2)
+                .find(|s| s[0] != s[1])
+                .is_some_and(|s| s[0] < s[1]),
+        };
+
+        levels.windows(2).enumerate().find_map(|(index, s)| {
+            let diff = s[0].abs_diff(s[1]);
+            if !(self.min_step..=self.max_step).contains(&diff) {
+                Some(Violation::StepOutOfRange { index, diff })
+            } else if s[0] == s[1] {
+                self.strict.then_some(Violation::NotMonotone { index })
+            } else if (s[0] < s[1]) != ascending {
+                Some(Violation::NotMonotone { index })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Checks whether the levels are safe after removing at most
+    /// `tolerated_removals` of them.
+    pub fn is_safe(&self, levels: &[u32]) -> bool {
+        match self.tolerated_removals {
+            0 | 1 => self
+                .directions()
+                .iter()
+                .any(|&ascending| self.is_safe_in_direction(levels, ascending)),
+            removals => self.is_safe_with_removals(levels, removals),
+        }
+    }
+
+    /// The directions to try, as whether the levels are ascending.
+    fn directions(&self) -> &'static [bool] {
+        match self.direction {
+            Direction::Ascending => &[true],
+            Direction::Descending => &[false],
+            Direction::Either => &[true, false],
+        }
+    }
+
+    #[inline]
+    fn is_valid_step(&self, a: u32, b: u32, ascending: bool) -> bool {
+        let monotone = if a == b {
+            !self.strict
+        } else {
+            (a < b) == ascending
+        };
+        monotone && (self.min_step..=self.max_step).contains(&a.abs_diff(b))
+    }
+
+    fn is_valid_without(&self, levels: &[u32], skip: usize, ascending: bool) -> bool {
+        let mut remaining = levels
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != skip)
+            .map(|(_, &level)| level);
+
+        let Some(mut prev) = remaining.next() else {
+            return true;
+        };
+        remaining.all(|level| {
+            let valid = self.is_valid_step(prev, level, ascending);
+            prev = level;
+            valid
+        })
+    }
+
+    /// Checks the levels in a fixed direction, removing at most one of them.
+    ///
+    /// The direction is fixed, as removing the first level might change it.
+    fn is_safe_in_direction(&self, levels: &[u32], ascending: bool) -> bool {
+        // Levels `idx` and `idx + 1` stay neighbours unless one of them is removed,
+        // so these are the only two candidates for the removal.
+        match levels
+            .windows(2)
+            .position(|s| !self.is_valid_step(s[0], s[1], ascending))
+        {
+            Some(idx) => {
+                self.tolerated_removals > 0
+                    && (self.is_valid_without(levels, idx, ascending)
+                        || self.is_valid_without(levels, idx + 1, ascending))
+            }
+            None => true,
+        }
+    }
+
+    /// Tries removing every combination of up to `removals` levels.
+    fn is_safe_with_removals(&self, levels: &[u32], removals: usize) -> bool {
+        if self.find_violation(levels).is_none() {
+            return true;
+        }
+        if removals == 0 {
+            return false;
+        }
+
+        (0..levels.len()).any(|idx| {
+            let mut candidate = levels.to_vec();
+            candidate.remove(idx);
+            self.is_safe_with_removals(&candidate, removals - 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&[u32]; 6] = [
+        &[7, 6, 4, 2, 1],
+        &[1, 2, 7, 8, 9],
+        &[9, 7, 6, 2, 1],
+        &[1, 3, 2, 4, 5],
+        &[8, 6, 4, 4, 1],
+        &[1, 3, 6, 7, 9],
+    ];
+
+    #[test]
+    fn test_part1() {
+        let policy = SafetyPolicy::part1();
+        let safe: Vec<_> = EXAMPLE.iter().map(|l| policy.is_safe(l)).collect();
+        assert_eq!(safe, [true, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_part2() {
+        let policy = SafetyPolicy::part2();
+        let safe: Vec<_> = EXAMPLE.iter().map(|l| policy.is_safe(l)).collect();
+        assert_eq!(safe, [true, false, false, true, true, true]);
+    }
+
+    #[test]
+    fn test_find_violation() {
+        let policy = SafetyPolicy::part1();
+        assert_eq!(policy.find_violation(&[7, 6, 4, 2, 1]), None);
+        assert_eq!(policy.find_violation(&[5]), None);
+        assert_eq!(
+            policy.find_violation(&[1, 2, 7, 8, 9]),
+            Some(Violation::StepOutOfRange { index: 1, diff: 5 })
+        );
+        assert_eq!(
+            policy.find_violation(&[8, 6, 4, 4, 1]),
+            Some(Violation::StepOutOfRange { index: 2, diff: 0 })
+        );
+        assert_eq!(
+            policy.find_violation(&[1, 3, 2, 4, 5]),
+            Some(Violation::NotMonotone { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_direction_and_strictness() {
+        let policy = SafetyPolicy {
+            direction: Direction::Descending,
+            ..SafetyPolicy::part1()
+        };
+        assert!(policy.is_safe(&[7, 6, 4, 2, 1]));
+        assert_eq!(
+            policy.find_violation(&[1, 3, 6, 7, 9]),
+            Some(Violation::NotMonotone { index: 0 })
+        );
+
+        let policy = SafetyPolicy {
+            min_step: 0,
+            strict: false,
+            ..SafetyPolicy::part1()
+        };
+        assert!(policy.is_safe(&[8, 6, 4, 4, 1]));
+        assert!(policy.is_safe(&[4, 4, 5, 6]));
+        assert!(!policy.is_safe(&[4, 4, 5, 3]));
+    }
+
+    #[test]
+    fn test_multiple_removals() {
+        let policy = SafetyPolicy {
+            tolerated_removals: 2,
+            ..SafetyPolicy::part1()
+        };
+        assert!(policy.is_safe(&[1, 9, 2, 9, 3]));
+        assert!(!SafetyPolicy::part2().is_safe(&[1, 9, 2, 9, 3]));
+    }
+
+    #[test]
+    fn test_fast_path_matches_exhaustive() {
+        const MAX_LEN: usize = 6;
+        const NR_VALUES: u32 = 5;
+
+        let mut policies = Vec::new();
+        for direction in [
+            Direction::Ascending,
+            Direction::Descending,
+            Direction::Either,
+        ] {
+            for strict in [true, false] {
+                for (min_step, max_step) in [(1, 3), (0, 2), (2, 4)] {
+                    for tolerated_removals in [0, 1] {
+                        policies.push(SafetyPolicy {
+                            min_step,
+                            max_step,
+                            strict,
+                            direction,
+                            tolerated_removals,
+                        });
+                    }
+                }
+            }
+        }
+
+        for len in 0..=MAX_LEN {
+            let mut levels = vec![0; len];
+            loop {
+                for policy in &policies {
+                    let expected = policy.is_safe_with_removals(&levels, policy.tolerated_removals);
+                    assert_eq!(
+                        policy.is_safe(&levels),
+                        expected,
+                        "{:?} {:?}",
+                        policy,
+                        levels
+                    );
+                }
+                // The reference implementation behind --exhaustive
+                assert_eq!(
+                    crate::is_safe_exhaustive(&levels),
+                    SafetyPolicy::part2().is_safe(&levels),
+                    "{:?}",
+                    levels
+                );
+
+                // Advance to the next combination of levels
+                let Some(idx) = levels.iter().rposition(|&l| l + 1 < NR_VALUES) else {
+                    break;
+                };
+                levels[idx] += 1;
+                levels[idx + 1..].fill(0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_str() {
+        let policy = SafetyPolicy::from_str(
+            "# reactor telemetry\nmax_step = 5\n\ndirection = ascending\ntolerated_removals = 2 # dampener\n",
+        )
+        .unwrap();
+        assert_eq!(
+            policy,
+            SafetyPolicy {
+                min_step: 1,
+                max_step: 5,
+                strict: true,
+                direction: Direction::Ascending,
+                tolerated_removals: 2,
+            }
+        );
+
+        assert_eq!(SafetyPolicy::from_str("").unwrap(), SafetyPolicy::part1());
+        assert!(SafetyPolicy::from_str("max_steps = 5").is_err());
+        assert!(SafetyPolicy::from_str("direction = sideways").is_err());
+        assert!(SafetyPolicy::from_str("min_step = 4").is_err());
+        assert!(SafetyPolicy::from_str("strict").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{BufRead, Write},
+};
+
+use anyhow::{Context, Result};
+
+use crate::policy::{SafetyPolicy, Violation};
+
+/// How a single report was classified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Safe,
+    /// Safe after removing some of the levels the policy tolerates.
+    Dampened,
+    Unsafe(Violation),
+}
+
+/// Running counts over all reports seen so far.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub safe: usize,
+    pub dampened: usize,
+    pub unsafe_: usize,
+    /// Number of unsafe reports per reason of their first violation.
+    pub reasons: BTreeMap<&'static str, usize>,
+    /// Number of reports per number of levels.
+    pub lengths: BTreeMap<usize, usize>,
+}
+
+impl Summary {
+    fn record(&mut self, class: Class, policy: &SafetyPolicy, levels: &[u32]) {
+        match class {
+            Class::Safe => self.safe += 1,
+            Class::Dampened => self.dampened += 1,
+            Class::Unsafe(violation) => {
+                self.unsafe_ += 1;
+                *self
+                    .reasons
+                    .entry(reason(policy, levels, violation))
+                    .or_default() += 1;
+            }
+        }
+        *self.lengths.entry(levels.len()).or_default() += 1;
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "safe: {}", self.safe)?;
+        writeln!(f, "safe with dampener: {}", self.dampened)?;
+        writeln!(f, "unsafe: {}", self.unsafe_)?;
+        writeln!(f, "failure reasons:")?;
+        for (reason, count) in &self.reasons {
+            writeln!(f, "  {}: {}", reason, count)?;
+        }
+        writeln!(f, "report lengths:")?;
+        for (len, count) in &self.lengths {
+            writeln!(f, "  {}: {}", len, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes the violation in terms of the policy that was broken.
+fn reason(policy: &SafetyPolicy, levels: &[u32], violation: Violation) -> &'static str {
+    match violation {
+        // Equal levels break a strict policy even if steps of 0 are allowed
+        Violation::NotMonotone { index } if levels[index] == levels[index + 1] => "no change",
+        Violation::NotMonotone { .. } => "not monotone",
+        Violation::StepOutOfRange { diff: 0, .. } => "no change",
+        Violation::StepOutOfRange { diff, .. } if diff < policy.min_step => "step too small",
+        Violation::StepOutOfRange { .. } => "step too large",
+    }
+}
+
+fn classify(policy: &SafetyPolicy, levels: &[u32]) -> Class {
+    match policy.find_violation(levels) {
+        None => Class::Safe,
+        Some(_) if policy.is_safe(levels) => Class::Dampened,
+        Some(violation) => Class::Unsafe(violation),
+    }
+}
+
+/// Classifies every report read from `reader` under the given policy, writing
+/// the running counts to `writer` after each one.
+///
+/// The line and level buffers are reused between reports, so memory usage
+/// doesn't grow with the size of the input.
+pub fn run(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    policy: &SafetyPolicy,
+) -> Result<Summary> {
+    let mut summary = Summary::default();
+    let mut line = String::new();
+    let mut levels = Vec::new();
+
+    for nr in 1.. {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        crate::parse_levels_into(&line, &mut levels).with_context(|| format!("Line {}", nr))?;
+        if levels.is_empty() {
+            continue;
+        }
+
+        let class = classify(policy, &levels);
+        summary.record(class, policy, &levels);
+
+        let label = match class {
+            Class::Safe => "safe",
+            Class::Dampened => "dampened",
+            Class::Unsafe(_) => "unsafe",
+        };
+        writeln!(
+            writer,
+            "{}: {} (safe: {}, dampened: {}, unsafe: {})",
+            nr, label, summary.safe, summary.dampened, summary.unsafe_
+        )?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let input = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\r\n1 3 2 4 5\n\n8 6 4 4 1\n1 3 6 7 9 12\n";
+        let mut output = Vec::new();
+
+        let summary = run(input.as_bytes(), &mut output, &SafetyPolicy::part2()).unwrap();
+
+        assert_eq!(
+            summary,
+            Summary {
+                safe: 2,
+                dampened: 2,
+                unsafe_: 2,
+                reasons: BTreeMap::from([("step too large", 2)]),
+                lengths: BTreeMap::from([(5, 5), (6, 1)]),
+            }
+        );
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "1: safe (safe: 1, dampened: 0, unsafe: 0)");
+        assert_eq!(lines[5], "7: safe (safe: 2, dampened: 2, unsafe: 2)");
+    }
+
+    #[test]
+    fn test_run_with_policy() {
+        let policy = SafetyPolicy {
+            max_step: 5,
+            tolerated_removals: 0,
+            ..SafetyPolicy::part1()
+        };
+        let summary = run(
+            "1 2 7 8 9\n1 3 2 4 5\n".as_bytes(),
+            std::io::sink(),
+            &policy,
+        )
+        .unwrap();
+        assert_eq!((summary.safe, summary.dampened, summary.unsafe_), (1, 0, 1));
+    }
+
+    #[test]
+    fn test_reasons_follow_policy() {
+        let policy = SafetyPolicy {
+            min_step: 2,
+            max_step: 4,
+            tolerated_removals: 0,
+            ..SafetyPolicy::part1()
+        };
+        let input = "1 2 4\n1 3 9\n1 1 3\n5 3 7\n";
+        let summary = run(input.as_bytes(), std::io::sink(), &policy).unwrap();
+        assert_eq!(
+            summary.reasons,
+            BTreeMap::from([
+                ("no change", 1),
+                ("not monotone", 1),
+                ("step too large", 1),
+                ("step too small", 1)
+            ])
+        );
+
+        let policy = SafetyPolicy {
+            min_step: 0,
+            tolerated_removals: 0,
+            ..SafetyPolicy::part1()
+        };
+        let summary = run("4 4 5\n".as_bytes(), std::io::sink(), &policy).unwrap();
+        assert_eq!(summary.reasons, BTreeMap::from([("no change", 1)]));
+    }
+
+    #[test]
+    fn test_run_invalid_line() {
+        let err = run(
+            "1 2 3\n1 x 3\n".as_bytes(),
+            std::io::sink(),
+            &SafetyPolicy::part2(),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Line 2");
+    }
+}
@@ -4,7 +4,7 @@ use input::INPUT;
 use regex::Regex;
 
 fn main() -> Result<()> {
-    let re = Regex::new(r"mul\((\d+),(\d+)\)").unwrap();
+    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
     let res: u64 = re
         .captures_iter(INPUT)
         .map(|caps| {
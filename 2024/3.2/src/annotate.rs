@@ -0,0 +1,174 @@
+use std::io::{self, Write};
+
+use crate::{
+    machine::Machine,
+    tokenizer::{Instruction, Token, Tokenizer},
+};
+
+const MARGIN_WIDTH: usize = 12;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const MUL: &str = "\x1b[1;32m";
+const DO: &str = "\x1b[1;34m";
+const DONT: &str = "\x1b[1;31m";
+
+/// How the annotations are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// ANSI colours: `mul` in green, `do()` in blue, `don't()` in red and
+    /// disabled regions dimmed.
+    Colour,
+    /// Markers for non-TTY output: enabled `mul`s are wrapped in `[]`,
+    /// disabled ones in `{}` and `do()`/`don't()` in `<>`.
+    Plain,
+}
+
+impl Style {
+    fn text(self, out: &mut String, text: &str, enabled: bool) {
+        match self {
+            Self::Colour if !enabled && !text.is_empty() => {
+                out.push_str(DIM);
+                out.push_str(text);
+                out.push_str(RESET);
+            }
+            _ => out.push_str(text),
+        }
+    }
+
+    fn token(self, out: &mut String, text: &str, instruction: Instruction, enabled: bool) {
+        let (open, close) = match (self, instruction) {
+            (Self::Colour, Instruction::Mul(..)) if enabled => (MUL, RESET),
+            (Self::Colour, Instruction::Mul(..)) => (DIM, RESET),
+            (Self::Colour, Instruction::Do) => (DO, RESET),
+            (Self::Colour, Instruction::Dont) => (DONT, RESET),
+            (Self::Plain, Instruction::Mul(..)) if enabled => ("[", "]"),
+            (Self::Plain, Instruction::Mul(..)) => ("{", "}"),
+            (Self::Plain, Instruction::Do | Instruction::Dont) => ("<", ">"),
+        };
+        out.push_str(open);
+        out.push_str(text);
+        out.push_str(close);
+    }
+}
+
+/// Writes the input with its instructions highlighted.
+///
+/// Every line is prefixed with the value of the accumulator at its end.
+pub fn annotate(
+    input: &str,
+    mut machine: Machine,
+    style: Style,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let tokens = Tokenizer::new(input.as_bytes()).collect::<io::Result<Vec<Token>>>()?;
+    let mut tokens = tokens.iter().peekable();
+
+    let mut line_start = 0;
+    for line in input.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let line_end = line_start + content.len();
+
+        let mut annotated = String::with_capacity(content.len());
+        let mut pos = line_start;
+        while let Some(token) = tokens.next_if(|t| t.offset < line_end) {
+            let enabled = machine.is_enabled();
+            style.text(&mut annotated, &input[pos..token.offset], enabled);
+
+            // Only `mul`s are styled by the state, `do()` and `don't()` have
+            // their own colours
+            machine.step(&token.instruction);
+            let end = token.offset + token.len;
+            style.token(
+                &mut annotated,
+                &input[token.offset..end],
+                token.instruction,
+                enabled,
+            );
+            pos = end;
+        }
+        style.text(&mut annotated, &input[pos..line_end], machine.is_enabled());
+
+        writeln!(
+            out,
+            "{:>width$} | {}",
+            machine.accumulator(),
+            annotated,
+            width = MARGIN_WIDTH
+        )?;
+        line_start += line.len();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)un\ndo()?mul(8,5))";
+
+    fn render(machine: Machine, style: Style) -> String {
+        let mut out = Vec::new();
+        annotate(EXAMPLE, machine, style, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_plain() {
+        let rendered = render(Machine::part2(), Style::Plain);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "           8 | x[mul(2,4)]&mul[3,7]!^<don't()>_{mul(5,5)}+mul(32,64]({mul(11,8)}un",
+                "          48 | <do()>?[mul(8,5)])",
+            ]
+        );
+
+        let rendered = render(Machine::part1(), Style::Plain);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "         121 | x[mul(2,4)]&mul[3,7]!^<don't()>_[mul(5,5)]+mul(32,64]([mul(11,8)]un",
+                "         161 | <do()>?[mul(8,5)])",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        let mut out = Vec::new();
+        annotate(
+            "mul(2,4)\r\ndon't()mul(1,1)\r\n",
+            Machine::part2(),
+            Style::Plain,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "           8 | [mul(2,4)]\n           8 | <don't()>{mul(1,1)}\n"
+        );
+    }
+
+    #[test]
+    fn test_colour() {
+        let rendered = render(Machine::part2(), Style::Colour);
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!(
+                "           8 | x{MUL}mul(2,4){RESET}&mul[3,7]!^{DONT}don't(){RESET}\
+                 {DIM}_{RESET}{DIM}mul(5,5){RESET}{DIM}+mul(32,64]({RESET}\
+                 {DIM}mul(11,8){RESET}{DIM}un{RESET}"
+            )
+        );
+        assert_eq!(
+            lines[1],
+            format!("          48 | {DO}do(){RESET}?{MUL}mul(8,5){RESET})")
+        );
+    }
+}
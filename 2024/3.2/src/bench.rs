@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Result};
+
+use crate::{generator, Engine};
+
+pub const MB: usize = 1024 * 1024;
+/// The input sizes used when `--bench` isn't given any.
+pub const DEFAULT_SIZES: [usize; 2] = [MB, 100 * MB];
+const SEED: u64 = 2024;
+
+/// Parses a size given in MB into bytes.
+pub fn parse_size(mb: &str) -> Result<usize> {
+    let size: f64 = mb.parse()?;
+    if !size.is_finite() || size <= 0.0 {
+        bail!(
+            "Invalid benchmark size '{}', expected a positive number of MB",
+            mb
+        );
+    }
+    Ok(((size * MB as f64) as usize).max(1))
+}
+
+/// Runs `f` `runs` times and returns its result and the fastest run.
+fn time<T>(runs: usize, mut f: impl FnMut() -> Result<T>) -> Result<(T, Duration)> {
+    let mut best = Duration::MAX;
+    let mut res = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        res = Some(f()?);
+        best = best.min(start.elapsed());
+    }
+    Ok((res.unwrap(), best))
+}
+
+/// Compares the throughput of all engines on generated inputs of the given
+/// sizes in bytes.
+///
+/// Only meaningful when built with `--release`.
+pub fn run(sizes: &[usize]) -> Result<()> {
+    for &size in sizes {
+        let generated = generator::generate(SEED, size);
+        let input = &generated.text;
+        let size = input.len();
+        let runs = (100 * MB / size.max(1)).clamp(1, 10);
+
+        for part1 in [true, false] {
+            let expected = if part1 {
+                generated.part1
+            } else {
+                generated.part2
+            };
+            for engine in Engine::ALL {
+                let (res, elapsed) = time(runs, || engine.solve(input, part1))?;
+                ensure!(
+                    res == expected,
+                    "{:?} returned {} instead of {}",
+                    engine,
+                    res,
+                    expected
+                );
+
+                println!(
+                    "{:>8.2} MB  part {}  {:<9}  {:>9.3} ms  {:>8.1} MB/s",
+                    size as f64 / MB as f64,
+                    if part1 { 1 } else { 2 },
+                    format!("{:?}", engine),
+                    elapsed.as_secs_f64() * 1000.0,
+                    size as f64 / MB as f64 / elapsed.as_secs_f64()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1").unwrap(), MB);
+        assert_eq!(parse_size("0.5").unwrap(), MB / 2);
+        for size in ["0", "-1", "nan", "inf", "big"] {
+            assert!(parse_size(size).is_err(), "{}", size);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+
+/// Bytes used for the noise between instructions.
+///
+/// Leaves out `m` and `d`, so noise never starts an instruction of its own.
+const NOISE: &[u8] = b"abcefghijklnopqrstuvwxyz0123456789 ,;:!?@#$%^&*()[]{}<>+-/'~\n";
+
+/// Small, seedable random number generator (SplitMix64).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn operand(&mut self) -> u64 {
+        // Favour short operands, as the real input does
+        match self.below(3) {
+            0 => self.below(10),
+            1 => self.below(100),
+            _ => self.below(1000),
+        }
+    }
+}
+
+/// A corrupted memory together with its answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub text: String,
+    pub part1: u64,
+    pub part2: u64,
+}
+
+impl Generated {
+    /// Writes the text to `path` and the answers to `path` with an added
+    /// `.expected` extension.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, &self.text)?;
+
+        let mut expected = path.as_os_str().to_owned();
+        expected.push(".expected");
+        fs::write(
+            expected,
+            format!("part1: {}\npart2: {}\n", self.part1, self.part2),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Generates about `size` bytes of corrupted memory from `seed`.
+///
+/// Every piece written is self-contained: near misses always contain the byte
+/// that breaks them, so no instruction can span two pieces and the answers
+/// are known without scanning the text.
+pub fn generate(seed: u64, size: usize) -> Generated {
+    let mut rng = Rng(seed);
+    let mut text = String::with_capacity(size + 16);
+    let mut part1 = 0;
+    let mut part2 = 0;
+    let mut enabled = true;
+
+    while text.len() < size {
+        match rng.below(20) {
+            0..=4 => {
+                let (a, b) = (rng.operand(), rng.operand());
+                text.push_str(&format!("mul({},{})", a, b));
+                part1 += a * b;
+                if enabled {
+                    part2 += a * b;
+                }
+            }
+            5 => {
+                text.push_str("do()");
+                enabled = true;
+            }
+            6 => {
+                text.push_str("don't()");
+                enabled = false;
+            }
+            7..=9 => near_miss(&mut rng, &mut text),
+            _ => {
+                for _ in 0..=rng.below(8) {
+                    text.push(NOISE[rng.below(NOISE.len() as u64) as usize] as char);
+                }
+            }
+        }
+    }
+
+    Generated { text, part1, part2 }
+}
+
+fn near_miss(rng: &mut Rng, text: &mut String) {
+    let (a, b) = (rng.operand(), rng.operand());
+    let near_miss = match rng.below(10) {
+        0 => format!("mul({}*", a),
+        1 => format!("mul ( {} , {} )", a, b),
+        2 => format!("mul({},{})", 1000 + a, b),
+        3 => format!("mul({},{})", a, 1000 + b),
+        4 => format!("mul[{},{}]", a, b),
+        5 => format!("mul({},{}]", a, b),
+        6 => format!("mul(-{},{})", a, b),
+        7 => "do_()".to_string(),
+        8 => "don't[]".to_string(),
+        _ => "don't ()".to_string(),
+    };
+    text.push_str(&near_miss);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{
+        machine::Machine,
+        tokenizer::{Token, Tokenizer},
+        Engine,
+    };
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(generate(42, 1000), generate(42, 1000));
+        assert_ne!(generate(42, 1000).text, generate(43, 1000).text);
+        assert!(generate(42, 1000).text.len() >= 1000);
+    }
+
+    #[test]
+    fn test_engines_match_expected() {
+        for seed in 0..50 {
+            let generated = generate(seed, 10_000);
+            for engine in Engine::ALL {
+                assert_eq!(
+                    engine.solve(&generated.text, true).unwrap(),
+                    generated.part1,
+                    "{:?} with seed {}",
+                    engine,
+                    seed
+                );
+                assert_eq!(
+                    engine.solve(&generated.text, false).unwrap(),
+                    generated.part2,
+                    "{:?} with seed {}",
+                    engine,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokenizer_chunk_boundaries() {
+        let generated = generate(7, 10_000);
+        for capacity in [1, 2, 3, 5, 7, 11] {
+            let tokens = Tokenizer::with_capacity(capacity, generated.text.as_bytes())
+                .collect::<io::Result<Vec<Token>>>()
+                .unwrap();
+            let res = Machine::part2().run(tokens.iter().map(|t| t.instruction));
+            assert_eq!(res, generated.part2, "capacity {}", capacity);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::tokenizer::Instruction;
+
+/// Something the [`Machine`] can execute.
+///
+/// This is the extension point for new instructions: wrap [`Instruction`] in
+/// your own enum and delegate to it for the instructions it already knows.
+pub trait Execute {
+    fn execute(&self, machine: &mut Machine);
+}
+
+impl Execute for Instruction {
+    fn execute(&self, machine: &mut Machine) {
+        match *self {
+            Instruction::Mul(a, b) => {
+                if machine.is_enabled() {
+                    let product = a.checked_mul(b).expect("Product overflows u64");
+                    machine.accumulate(product);
+                }
+            }
+            Instruction::Do => machine.set_enabled(true),
+            Instruction::Dont => machine.set_enabled(false),
+        }
+    }
+}
+
+/// Interpreter for the instructions found in the corrupted memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    honour_conditionals: bool,
+    enabled: bool,
+    accumulator: u64,
+}
+
+impl Machine {
+    pub fn new(honour_conditionals: bool) -> Self {
+        Self {
+            honour_conditionals,
+            enabled: true,
+            accumulator: 0,
+        }
+    }
+
+    /// The machine of part 1, which ignores `do()` and `don't()`.
+    pub fn part1() -> Self {
+        Self::new(false)
+    }
+
+    /// The machine of part 2, which honours `do()` and `don't()`.
+    pub fn part2() -> Self {
+        Self::new(true)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables the machine, unless it ignores conditionals.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if self.honour_conditionals {
+            self.enabled = enabled;
+        }
+    }
+
+    pub fn accumulator(&self) -> u64 {
+        self.accumulator
+    }
+
+    pub fn accumulate(&mut self, value: u64) {
+        self.accumulator = self
+            .accumulator
+            .checked_add(value)
+            .expect("Accumulator overflows u64");
+    }
+
+    pub fn step(&mut self, instruction: &impl Execute) {
+        instruction.execute(self);
+    }
+
+    /// Executes all instructions and returns the final accumulator value.
+    pub fn run<I: Execute>(&mut self, instructions: impl IntoIterator<Item = I>) -> u64 {
+        for instruction in instructions {
+            self.step(&instruction);
+        }
+        self.accumulator()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [Instruction; 6] = [
+        Instruction::Mul(2, 4),
+        Instruction::Dont,
+        Instruction::Mul(5, 5),
+        Instruction::Mul(11, 8),
+        Instruction::Do,
+        Instruction::Mul(8, 5),
+    ];
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(Machine::part1().run(EXAMPLE), 161);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(Machine::part2().run(EXAMPLE), 48);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut machine = Machine::part2();
+        machine.step(&Instruction::Mul(2, 4));
+        assert_eq!(machine.accumulator(), 8);
+        machine.step(&Instruction::Dont);
+        assert!(!machine.is_enabled());
+        machine.step(&Instruction::Mul(5, 5));
+        assert_eq!(machine.accumulator(), 8);
+        machine.step(&Instruction::Do);
+        assert!(machine.is_enabled());
+    }
+
+    #[test]
+    fn test_extension() {
+        enum Extended {
+            Base(Instruction),
+            Reset,
+        }
+
+        impl Execute for Extended {
+            fn execute(&self, machine: &mut Machine) {
+                match self {
+                    Self::Base(instruction) => instruction.execute(machine),
+                    Self::Reset => *machine = Machine::new(machine.honour_conditionals),
+                }
+            }
+        }
+
+        let instructions = [
+            Extended::Base(Instruction::Mul(2, 4)),
+            Extended::Reset,
+            Extended::Base(Instruction::Mul(3, 3)),
+        ];
+        assert_eq!(Machine::part1().run(instructions), 9);
+    }
+}
@@ -1,13 +1,63 @@
+mod annotate;
+mod bench;
+mod generator;
 mod input;
-use anyhow::Result;
+mod machine;
+mod scanner;
+mod tokenizer;
+
+use std::io::{self, IsTerminal, Read};
+
+use annotate::Style;
+use anyhow::{anyhow, bail, Result};
 use input::INPUT;
+use machine::Machine;
 use regex::Regex;
+use scanner::Scanner;
+use tokenizer::Tokenizer;
 
-fn main() -> Result<()> {
-    let re = Regex::new(r"mul\((\d+),(\d+)\)|(?<do>do\(\))|(?<dont>don't\(\))").unwrap();
+/// The ways of finding the instructions in the corrupted memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Engine {
+    Tokenizer,
+    Scanner,
+    Regex,
+}
+
+impl Engine {
+    const ALL: [Engine; 3] = [Engine::Tokenizer, Engine::Scanner, Engine::Regex];
+
+    fn solve(self, input: &str, part1: bool) -> Result<u64> {
+        let machine = if part1 {
+            Machine::part1()
+        } else {
+            Machine::part2()
+        };
+
+        match self {
+            Self::Tokenizer => sum_with_tokenizer(input.as_bytes(), machine),
+            Self::Scanner => Ok(sum_with_scanner(input.as_bytes(), machine)),
+            Self::Regex if part1 => Ok(part1_with_regex(input)),
+            Self::Regex => Ok(part2_with_regex(input)),
+        }
+    }
+}
+
+fn part1_with_regex(input: &str) -> u64 {
+    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
+    re.captures_iter(input)
+        .map(|caps| {
+            let (_, [a, b]) = caps.extract();
+            let (a, b): (u64, u64) = (a.parse().unwrap(), b.parse().unwrap());
+            a * b
+        })
+        .sum()
+}
+
+fn part2_with_regex(input: &str) -> u64 {
+    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)|(?<do>do\(\))|(?<dont>don't\(\))").unwrap();
     let mut mul_enabled = true;
-    let res: u64 = re
-        .captures_iter(INPUT)
+    re.captures_iter(input)
         .filter_map(|caps| {
             if caps.name("do").is_some() {
                 mul_enabled = true;
@@ -26,9 +76,123 @@ fn main() -> Result<()> {
             let b: u64 = caps.get(2).unwrap().as_str().parse().unwrap();
             Some(a * b)
         })
-        .sum();
+        .sum()
+}
+
+fn sum_with_tokenizer(reader: impl Read, mut machine: Machine) -> Result<u64> {
+    for token in Tokenizer::new(reader) {
+        machine.step(&token?.instruction);
+    }
+    Ok(machine.accumulator())
+}
+
+fn sum_with_scanner(input: &[u8], mut machine: Machine) -> u64 {
+    machine.run(Scanner::new(input).map(|t| t.instruction))
+}
+
+fn print_rejections(reader: impl Read) -> Result<()> {
+    let mut tokenizer = Tokenizer::new(reader).with_rejections();
+    for token in tokenizer.by_ref() {
+        token?;
+    }
+
+    for rejection in tokenizer.rejections().unwrap() {
+        println!(
+            "{}: {:?}: {}",
+            rejection.offset, rejection.fragment, rejection.reason
+        );
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let mut part1 = false;
+    let mut engine = Engine::Tokenizer;
+    let mut rejections = false;
+    let mut annotate = false;
+    let mut bench = false;
+    let mut bench_sizes = Vec::new();
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part1" => part1 = true,
+            "--regex" => engine = Engine::Regex,
+            "--scanner" => engine = Engine::Scanner,
+            "--rejections" => rejections = true,
+            "--annotate" => annotate = true,
+            "--bench" => {
+                bench = true;
+                // The size in MB is optional
+                if let Some(size) = args.next_if(|arg| arg.parse::<f64>().is_ok()) {
+                    bench_sizes.push(bench::parse_size(&size)?);
+                }
+            }
+            "--generate" => {
+                let mut next = |name| args.next().ok_or(anyhow!("--generate expects {}", name));
+                let seed = next("a seed")?.parse()?;
+                let size = next("a size in bytes")?.parse()?;
+                let path = next("a path")?;
+                return generator::generate(seed, size).write(path);
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
+
+    if bench {
+        if bench_sizes.is_empty() {
+            bench_sizes.extend(bench::DEFAULT_SIZES);
+        }
+        return bench::run(&bench_sizes);
+    }
+
+    if annotate {
+        let machine = if part1 {
+            Machine::part1()
+        } else {
+            Machine::part2()
+        };
+        let stdout = io::stdout();
+        let style = if stdout.is_terminal() {
+            Style::Colour
+        } else {
+            Style::Plain
+        };
+        annotate::annotate(INPUT, machine, style, stdout.lock())?;
+        return Ok(());
+    }
+
+    if rejections {
+        print_rejections(INPUT.as_bytes())?;
+    }
+
+    let res = engine.solve(INPUT, part1)?;
 
     println!("{}", res);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        for engine in Engine::ALL {
+            assert_eq!(engine.solve(input, true).unwrap(), 161, "{:?}", engine);
+            assert_eq!(engine.solve(input, false).unwrap(), 48, "{:?}", engine);
+        }
+    }
+
+    #[test]
+    fn test_engines_agree() {
+        for part1 in [true, false] {
+            let expected = Engine::Regex.solve(INPUT, part1).unwrap();
+            for engine in Engine::ALL {
+                assert_eq!(engine.solve(INPUT, part1).unwrap(), expected);
+            }
+        }
+    }
+}
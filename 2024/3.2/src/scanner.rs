@@ -0,0 +1,129 @@
+use memchr::memchr2;
+
+use crate::tokenizer::{Instruction, Token};
+
+const MAX_DIGITS: usize = 3;
+
+/// Iterates over the instructions in an in-memory input.
+///
+/// Uses `memchr` to jump straight to the next `m` or `d`, which are the only
+/// bytes an instruction can start with, and only then checks for a match.
+pub struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let offset = self.pos + memchr2(b'm', b'd', &self.input[self.pos..])?;
+            let candidate = &self.input[offset..];
+
+            let found = match candidate[0] {
+                b'm' => match_mul(candidate),
+                _ => match_do(candidate),
+            };
+
+            match found {
+                Some((instruction, len)) => {
+                    self.pos = offset + len;
+                    return Some(Token {
+                        offset,
+                        len,
+                        instruction,
+                    });
+                }
+                None => self.pos = offset + 1,
+            }
+        }
+    }
+}
+
+/// Matches `mul(a,b)`, returning the instruction and its length.
+#[inline]
+fn match_mul(s: &[u8]) -> Option<(Instruction, usize)> {
+    let rest = s.strip_prefix(b"mul(")?;
+    let (a, a_len) = match_operand(rest)?;
+    let rest = rest[a_len..].strip_prefix(b",")?;
+    let (b, b_len) = match_operand(rest)?;
+    if !rest[b_len..].starts_with(b")") {
+        return None;
+    }
+
+    Some((Instruction::Mul(a, b), 4 + a_len + 1 + b_len + 1))
+}
+
+/// Matches an operand of 1 to 3 digits.
+///
+/// A fourth digit doesn't have to be checked for, as the byte following the
+/// operand has to be a delimiter anyway.
+#[inline]
+fn match_operand(s: &[u8]) -> Option<(u64, usize)> {
+    let len = s
+        .iter()
+        .take(MAX_DIGITS)
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if len == 0 {
+        return None;
+    }
+
+    let value = s[..len]
+        .iter()
+        .fold(0, |acc, &b| acc * 10 + (b - b'0') as u64);
+    Some((value, len))
+}
+
+#[inline]
+fn match_do(s: &[u8]) -> Option<(Instruction, usize)> {
+    if s.starts_with(b"do()") {
+        Some((Instruction::Do, 4))
+    } else if s.starts_with(b"don't()") {
+        Some((Instruction::Dont, 7))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::{input::INPUT, tokenizer::Tokenizer};
+
+    fn tokenize(input: &str) -> Vec<Token> {
+        Tokenizer::new(input.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    fn scan(input: &str) -> Vec<Token> {
+        Scanner::new(input.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_matches_tokenizer() {
+        for input in [
+            INPUT,
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))",
+            "mumul(1,2)ddo()mul(3,mul(4,5)don(don't()",
+            "mul(1234,5)mul(123,4567)mul(0,999)mul(,1)mul(1,)",
+            "",
+            "m",
+            "mul(1,2",
+            "don't(",
+            "do()",
+        ] {
+            assert_eq!(scan(input), tokenize(input), "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+const MAX_DIGITS: u8 = 3;
+
+/// An instruction found in the corrupted memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(u64, u64),
+    Do,
+    Dont,
+}
+
+/// An instruction together with the byte range it was found at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub offset: usize,
+    pub len: usize,
+    pub instruction: Instruction,
+}
+
+/// Why a partially matched instruction was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    UnexpectedByte(u8),
+    /// Operands have to consist of 1 to 3 digits.
+    OperandTooLong,
+    UnexpectedEnd,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedByte(byte) => write!(f, "unexpected {:?}", *byte as char),
+            Self::OperandTooLong => write!(f, "operand has more than {} digits", MAX_DIGITS),
+            Self::UnexpectedEnd => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+/// A fragment that looked like an instruction but turned out not to be one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub offset: usize,
+    pub fragment: String,
+    pub reason: Reason,
+}
+
+/// How much of an instruction has been matched so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    M,
+    Mu,
+    Mul,
+    MulOpen,
+    /// The value and number of digits of the first operand.
+    FirstOperand(u64, u8),
+    Comma(u64),
+    SecondOperand(u64, u64, u8),
+    D,
+    Do,
+    DoOpen,
+    Don,
+    DonApostrophe,
+    Dont,
+    DontOpen,
+}
+
+enum Step {
+    Continue(State),
+    Complete(Instruction),
+    Fail(Reason),
+}
+
+impl State {
+    fn advance(self, byte: u8) -> Step {
+        let next = match (self, byte) {
+            (Self::Start, b'm') => Self::M,
+            (Self::Start, b'd') => Self::D,
+            (Self::M, b'u') => Self::Mu,
+            (Self::Mu, b'l') => Self::Mul,
+            (Self::Mul, b'(') => Self::MulOpen,
+            (Self::MulOpen, b'0'..=b'9') => Self::FirstOperand(digit(byte), 1),
+            (Self::FirstOperand(_, MAX_DIGITS), b'0'..=b'9') => {
+                return Step::Fail(Reason::OperandTooLong)
+            }
+            (Self::FirstOperand(a, digits), b'0'..=b'9') => {
+                Self::FirstOperand(a * 10 + digit(byte), digits + 1)
+            }
+            (Self::FirstOperand(a, _), b',') => Self::Comma(a),
+            (Self::Comma(a), b'0'..=b'9') => Self::SecondOperand(a, digit(byte), 1),
+            (Self::SecondOperand(_, _, MAX_DIGITS), b'0'..=b'9') => {
+                return Step::Fail(Reason::OperandTooLong)
+            }
+            (Self::SecondOperand(a, b, digits), b'0'..=b'9') => {
+                Self::SecondOperand(a, b * 10 + digit(byte), digits + 1)
+            }
+            (Self::SecondOperand(a, b, _), b')') => return Step::Complete(Instruction::Mul(a, b)),
+            (Self::D, b'o') => Self::Do,
+            (Self::Do, b'(') => Self::DoOpen,
+            (Self::DoOpen, b')') => return Step::Complete(Instruction::Do),
+            (Self::Do, b'n') => Self::Don,
+            (Self::Don, b'\'') => Self::DonApostrophe,
+            (Self::DonApostrophe, b't') => Self::Dont,
+            (Self::Dont, b'(') => Self::DontOpen,
+            (Self::DontOpen, b')') => return Step::Complete(Instruction::Dont),
+            _ => return Step::Fail(Reason::UnexpectedByte(byte)),
+        };
+        Step::Continue(next)
+    }
+
+    /// Whether the name of an instruction has been matched completely, which
+    /// makes a failure worth reporting.
+    fn is_near_miss(self) -> bool {
+        !matches!(
+            self,
+            Self::Start | Self::M | Self::Mu | Self::D | Self::Don | Self::DonApostrophe
+        )
+    }
+}
+
+#[inline]
+fn digit(byte: u8) -> u64 {
+    (byte - b'0') as u64
+}
+
+/// Recognises instructions one byte at a time.
+///
+/// None of the partially matched instructions contains a proper suffix which
+/// is also the prefix of an instruction, so a failed match only has to be
+/// retried from the byte that broke it.
+#[derive(Debug, Clone)]
+struct Matcher {
+    state: State,
+    start: usize,
+    /// The bytes of the current partial match.
+    fragment: Vec<u8>,
+    rejections: Option<Vec<Rejection>>,
+}
+
+impl Matcher {
+    fn new() -> Self {
+        Self {
+            state: State::Start,
+            start: 0,
+            fragment: Vec::new(),
+            rejections: None,
+        }
+    }
+
+    fn feed(&mut self, byte: u8, offset: usize) -> Option<Token> {
+        if self.state == State::Start {
+            self.start = offset;
+        }
+
+        match self.state.advance(byte) {
+            Step::Continue(next) => {
+                self.state = next;
+                self.fragment.push(byte);
+                None
+            }
+            Step::Complete(instruction) => {
+                self.reset();
+                Some(Token {
+                    offset: self.start,
+                    len: offset + 1 - self.start,
+                    instruction,
+                })
+            }
+            Step::Fail(_) if self.state == State::Start => None,
+            Step::Fail(reason) => {
+                self.fragment.push(byte);
+                self.reject(reason);
+                // Retry the byte as the start of a new instruction
+                self.feed(byte, offset)
+            }
+        }
+    }
+
+    /// Rejects a partial match left over at the end of the input.
+    fn finish(&mut self) {
+        if self.state != State::Start {
+            self.reject(Reason::UnexpectedEnd);
+        }
+    }
+
+    fn reject(&mut self, reason: Reason) {
+        if let Some(rejections) = &mut self.rejections {
+            if self.state.is_near_miss() {
+                rejections.push(Rejection {
+                    offset: self.start,
+                    fragment: String::from_utf8_lossy(&self.fragment).into_owned(),
+                    reason,
+                });
+            }
+        }
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Start;
+        self.fragment.clear();
+    }
+}
+
+/// Iterates over the instructions read from any [`Read`] source.
+///
+/// The source is read in chunks, instructions split across two chunks are
+/// recognised all the same.
+pub struct Tokenizer<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    /// Offset of `buf[0]` in the whole input.
+    offset: usize,
+    matcher: Matcher,
+}
+
+impl<R: Read> Tokenizer<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        assert!(capacity > 0, "Capacity must not be zero");
+        Self {
+            reader,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            offset: 0,
+            matcher: Matcher::new(),
+        }
+    }
+
+    /// Keeps track of near misses, which can be retrieved with
+    /// [`Tokenizer::rejections`].
+    pub fn with_rejections(mut self) -> Self {
+        self.matcher.rejections = Some(Vec::new());
+        self
+    }
+
+    /// The near misses found so far, if they are being tracked.
+    pub fn rejections(&self) -> Option<&[Rejection]> {
+        self.matcher.rejections.as_deref()
+    }
+
+    /// Refills the buffer, returning `false` at the end of the input.
+    fn fill_buf(&mut self) -> io::Result<bool> {
+        self.offset += self.len;
+        self.pos = 0;
+        self.len = loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(len) => break len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(self.len > 0)
+    }
+}
+
+impl<R: Read> Iterator for Tokenizer<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.pos == self.len {
+                match self.fill_buf() {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.matcher.finish();
+                        return None;
+                    }
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            let byte = self.buf[self.pos];
+            let offset = self.offset + self.pos;
+            self.pos += 1;
+
+            if let Some(token) = self.matcher.feed(byte, offset) {
+                return Some(Ok(token));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::INPUT;
+    use regex::Regex;
+
+    fn tokenize(input: &str, capacity: usize) -> Vec<Token> {
+        Tokenizer::with_capacity(capacity, input.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    fn tokenize_with_regex(input: &str) -> Vec<Token> {
+        let re =
+            Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)|(?<do>do\(\))|(?<dont>don't\(\))").unwrap();
+        re.captures_iter(input)
+            .map(|caps| {
+                let whole = caps.get(0).unwrap();
+                let instruction = if caps.name("do").is_some() {
+                    Instruction::Do
+                } else if caps.name("dont").is_some() {
+                    Instruction::Dont
+                } else {
+                    let a = caps.get(1).unwrap().as_str().parse().unwrap();
+                    let b = caps.get(2).unwrap().as_str().parse().unwrap();
+                    Instruction::Mul(a, b)
+                };
+                Token {
+                    offset: whole.start(),
+                    len: whole.len(),
+                    instruction,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let instructions: Vec<_> = tokenize(input, DEFAULT_CAPACITY)
+            .iter()
+            .map(|t| t.instruction)
+            .collect();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Mul(2, 4),
+                Instruction::Dont,
+                Instruction::Mul(5, 5),
+                Instruction::Mul(11, 8),
+                Instruction::Do,
+                Instruction::Mul(8, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restart_on_failure() {
+        let tokens = tokenize("mumul(1,2)ddo()mul(3,mul(4,5)don(don't()", 3);
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    offset: 2,
+                    len: 8,
+                    instruction: Instruction::Mul(1, 2)
+                },
+                Token {
+                    offset: 11,
+                    len: 4,
+                    instruction: Instruction::Do
+                },
+                Token {
+                    offset: 21,
+                    len: 8,
+                    instruction: Instruction::Mul(4, 5)
+                },
+                Token {
+                    offset: 33,
+                    len: 7,
+                    instruction: Instruction::Dont
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operand_width() {
+        let tokens = tokenize("mul(1234,5)mul(123,4567)mul(0,999)mul(,1)", 4);
+        assert_eq!(
+            tokens,
+            vec![Token {
+                offset: 24,
+                len: 10,
+                instruction: Instruction::Mul(0, 999)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rejections() {
+        let input = "mul(4*mul ( 2 , 4 )mul(1234,5)mumul(1,2)do_don't)from()don't(";
+        let mut tokenizer = Tokenizer::with_capacity(3, input.as_bytes()).with_rejections();
+        let tokens: Vec<_> = tokenizer.by_ref().collect::<io::Result<_>>().unwrap();
+        assert_eq!(tokens.len(), 1);
+
+        let rejections: Vec<_> = tokenizer
+            .rejections()
+            .unwrap()
+            .iter()
+            .map(|r| (r.offset, r.fragment.as_str(), r.reason))
+            .collect();
+        assert_eq!(
+            rejections,
+            vec![
+                (0, "mul(4*", Reason::UnexpectedByte(b'*')),
+                (6, "mul ", Reason::UnexpectedByte(b' ')),
+                (19, "mul(1234", Reason::OperandTooLong),
+                (40, "do_", Reason::UnexpectedByte(b'_')),
+                (43, "don't)", Reason::UnexpectedByte(b')')),
+                (55, "don't(", Reason::UnexpectedEnd),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_regex() {
+        let expected = tokenize_with_regex(INPUT);
+        assert!(!expected.is_empty());
+
+        // Small capacities split most of the instructions across chunks
+        for capacity in [1, 2, 3, 5, 7, 64, DEFAULT_CAPACITY] {
+            assert_eq!(tokenize(INPUT, capacity), expected, "capacity {}", capacity);
+        }
+    }
+}
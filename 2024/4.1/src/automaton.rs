@@ -0,0 +1,116 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use anyhow::{bail, Result};
+
+use crate::search::{Grid, Match};
+
+/// Finds a list of words, read forwards and backwards, in lines of text.
+///
+/// All words and their reversals are compiled into a single Aho-Corasick
+/// automaton, so every line is only passed over once, no matter how many
+/// words there are.
+pub struct WordAutomaton<'w> {
+    /// Word `i` is pattern `2 * i`, its reversal pattern `2 * i + 1`.
+    automaton: AhoCorasick,
+    words: Vec<&'w str>,
+}
+
+impl<'w> WordAutomaton<'w> {
+    pub fn new(words: &[&'w str]) -> Result<Self> {
+        let mut patterns = Vec::with_capacity(2 * words.len());
+        for word in words {
+            // An empty word would match between every pair of letters
+            if word.is_empty() {
+                bail!("Words can't be empty");
+            }
+            patterns.push(word.to_string());
+            patterns.push(word.chars().rev().collect());
+        }
+
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::Standard)
+            .build(&patterns)?;
+
+        Ok(Self {
+            automaton,
+            words: words.to_vec(),
+        })
+    }
+
+    /// Counts the occurences of each word in the text.
+    ///
+    /// Words may overlap each other, and are never matched across lines.
+    pub fn counts(&self, text: &str) -> Vec<usize> {
+        let mut counts = vec![0; self.words.len()];
+        for line in text.lines() {
+            for m in self.automaton.find_overlapping_iter(line) {
+                counts[m.pattern().as_usize() / 2] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Counts the occurences of all words in the text.
+    pub fn count(&self, text: &str) -> usize {
+        self.counts(text).iter().sum()
+    }
+
+    /// Finds every occurrence of the words in all eight directions of the
+    /// grid, ordered by where they start, then by word and direction.
+    ///
+    /// Every line of the grid is fed through the automaton once. A reversed
+    /// word found on a line is the word itself read in the opposite
+    /// direction, starting from the end of the match.
+    pub fn find(&self, grid: &Grid) -> Vec<Match<'w>> {
+        let mut matches = Vec::new();
+        let mut letters = Vec::new();
+        for line in grid.grid_lines() {
+            letters.clear();
+            letters.extend((0..line.len).map(|i| {
+                let (row, col) = line.cell(i);
+                grid.get(row, col).unwrap()
+            }));
+
+            for m in self.automaton.find_overlapping_iter(&letters) {
+                let pattern = m.pattern().as_usize();
+                let (start, direction) = if pattern % 2 == 0 {
+                    (m.start(), line.direction)
+                } else {
+                    (m.end() - 1, line.direction.opposite())
+                };
+                let (row, col) = line.cell(start);
+                let word = pattern / 2;
+                matches.push((
+                    word,
+                    Match {
+                        row,
+                        col,
+                        direction,
+                        word: self.words[word],
+                    },
+                ));
+            }
+        }
+
+        matches.sort_unstable_by_key(|&(word, m)| (m.row, m.col, word, m.direction));
+        matches.into_iter().map(|(_, m)| m).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts() {
+        let automaton = WordAutomaton::new(&["XMAS", "AM", "ABA"]).unwrap();
+        assert_eq!(automaton.counts("XMASAMX\nSAMX\nA\nMX"), vec![3, 3, 0]);
+        // Palindromes are found forwards and backwards
+        assert_eq!(automaton.counts("ABABA"), vec![0, 0, 4]);
+    }
+
+    #[test]
+    fn test_empty_word() {
+        assert!(WordAutomaton::new(&["XMAS", ""]).is_err());
+        assert!(Grid::from_str("XMAS").unwrap().find_words(&[""]).is_err());
+    }
+}
@@ -1,13 +1,27 @@
+mod automaton;
+mod search;
+
 use std::fs::{self};
 
-use anyhow::Result;
-use regex::Regex;
+use anyhow::{anyhow, bail, Result};
+use automaton::WordAutomaton;
+use search::{parse_rows, Grid};
 
-fn count_occurences(input: &str) -> usize {
-    let re = Regex::new(r"XMAS").unwrap();
-    let rev = Regex::new(r"SAMX").unwrap();
+/// Counts the occurences of the words in all eight directions of the grid.
+fn count_occurences(input: &str, words: &[&str]) -> Result<usize> {
+    Ok(Grid::from_str(input)?.find_words(words)?.len())
+}
 
-    re.find_iter(input).count() + rev.find_iter(input).count()
+/// Reads a word list with one word per line.
+fn parse_words(input: &str) -> Result<Vec<String>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, word)| match word.trim() {
+            "" => bail!("Line {}: the word list can't contain blank lines", i + 1),
+            word => Ok(word.to_string()),
+        })
+        .collect()
 }
 
 fn transpose(input: &str, line_len: usize, nr_lines: usize) -> String {
@@ -22,6 +36,13 @@ fn transpose(input: &str, line_len: usize, nr_lines: usize) -> String {
     transposed
 }
 
+/// The length of the `i`th (1 based) diagonal of a `nr_lines` by `line_len` grid.
+fn nr_elements_on_diagonal(i: isize, line_len: usize, nr_lines: isize) -> isize {
+    i.min(nr_lines)
+        .min(line_len as isize)
+        .min(nr_lines + line_len as isize - i)
+}
+
 fn get_diagonals(input: &str, line_len: usize, nr_lines: usize) -> String {
     let input = input.as_bytes();
     let mut diagonals = String::with_capacity(input.len() + nr_lines);
@@ -30,7 +51,7 @@ fn get_diagonals(input: &str, line_len: usize, nr_lines: usize) -> String {
 
     for i in 1..=nr_diagonals {
         let i = i as isize;
-        let mut nr_elements = (i).min(nr_lines) + (nr_lines - i).min(0);
+        let mut nr_elements = nr_elements_on_diagonal(i, line_len, nr_lines);
         let mut row = (i - 1).min(nr_lines - 1);
         let mut col = (i - nr_lines).max(0);
         while nr_elements > 0 {
@@ -54,7 +75,7 @@ fn get_anti_diagonals(input: &str, line_len: usize, nr_lines: usize) -> String {
 
     for i in 1..=nr_diagonals {
         let i = i as isize;
-        let mut nr_elements = (i).min(nr_lines) + (nr_lines - i).min(0);
+        let mut nr_elements = nr_elements_on_diagonal(i, line_len, nr_lines);
         let mut row = (nr_lines - i).max(0);
         let mut col = (i - nr_lines).max(0);
         while nr_elements > 0 {
@@ -70,26 +91,56 @@ fn get_anti_diagonals(input: &str, line_len: usize, nr_lines: usize) -> String {
     diagonals
 }
 
-fn main() -> Result<()> {
-    let input = fs::read_to_string("input.txt")?;
-    let line_len = input.find('\n').unwrap();
-    let row_len = line_len + 1;
-    let nr_lines = input.len() / row_len;
+/// Counts the occurences by materialising the columns and diagonals.
+fn count_with_traversals(input: &str, automaton: &WordAutomaton) -> Result<usize> {
+    // The traversals rely on every row being terminated by a single '\n'
+    let rows = parse_rows(input)?;
+    let line_len = rows[0].len();
+    let nr_lines = rows.len();
+    let input: String = rows.iter().flat_map(|row| [row, "\n"]).collect();
+    let input = input.as_str();
 
     // Find all horizontal occurences
-    let mut count = count_occurences(&input);
+    let mut count = automaton.count(input);
 
     // Find all vertical occurences
-    let transposed = transpose(&input, line_len, nr_lines);
-    count += count_occurences(&transposed);
+    let transposed = transpose(input, line_len, nr_lines);
+    count += automaton.count(&transposed);
 
     // Find all occurences in the diagonal input
-    let diagonals = get_diagonals(&input, line_len, nr_lines);
-    count += count_occurences(&diagonals);
+    let diagonals = get_diagonals(input, line_len, nr_lines);
+    count += automaton.count(&diagonals);
 
     // Find all occurences in the anti-diagonal input
-    let anti_diagonals = get_anti_diagonals(&input, line_len, nr_lines);
-    count += count_occurences(&anti_diagonals);
+    let anti_diagonals = get_anti_diagonals(input, line_len, nr_lines);
+    count += automaton.count(&anti_diagonals);
+
+    Ok(count)
+}
+
+fn main() -> Result<()> {
+    let mut words = vec!["XMAS".to_string()];
+    let mut traversals = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--traversals" => traversals = true,
+            "--words" => {
+                let path = args.next().ok_or(anyhow!("--words expects a file path"))?;
+                words = parse_words(&fs::read_to_string(path)?)?;
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
+    let words: Vec<_> = words.iter().map(String::as_str).collect();
+
+    let input = fs::read_to_string("input.txt")?;
+
+    let count = if traversals {
+        count_with_traversals(&input, &WordAutomaton::new(&words)?)?
+    } else {
+        count_occurences(&input, &words)?
+    };
 
     println!("count: {}", count);
 
@@ -103,7 +154,107 @@ mod tests {
     #[test]
     fn test_count_occurences() {
         let input = "XMAS\nSAMX\nXMAS\nSAMX";
-        assert_eq!(count_occurences(input), 4);
+        assert_eq!(count_occurences(input, &["XMAS"]).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_parse_words() {
+        assert_eq!(parse_words("XMAS\n SAMX \r\n").unwrap(), ["XMAS", "SAMX"]);
+        let err = parse_words("XMAS\n\nSAMX\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 2: the word list can't contain blank lines"
+        );
+    }
+
+    #[test]
+    fn test_count_with_traversals() {
+        let input = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\n\
+                     XXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX\n";
+        let automaton = WordAutomaton::new(&["XMAS"]).unwrap();
+        assert_eq!(count_occurences(input, &["XMAS"]).unwrap(), 18);
+        assert_eq!(count_with_traversals(input, &automaton).unwrap(), 18);
+    }
+
+    #[test]
+    fn test_many_words() {
+        // Simple linear congruential generator to get a reproducible puzzle
+        let mut state = 42u64;
+        let mut letter = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            b"XMAS"[(state >> 33) as usize % 4] as char
+        };
+
+        let size = 60;
+        let mut input = String::new();
+        for _ in 0..size {
+            input.extend((0..size).map(|_| letter()));
+            input.push('\n');
+        }
+
+        let words: Vec<String> = (0..500)
+            .map(|i| (0..1 + i % 6).map(|_| letter()).collect())
+            .collect();
+        let words: Vec<_> = words.iter().map(String::as_str).collect();
+
+        let automaton = WordAutomaton::new(&words).unwrap();
+        assert_eq!(
+            count_with_traversals(&input, &automaton).unwrap(),
+            count_occurences(&input, &words).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_non_square_grids() {
+        let automaton = WordAutomaton::new(&["XMAS"]).unwrap();
+        for input in [
+            "XMASX\nMMASM\nAAASA\n",
+            "XMA\nMMA\nAAA\nSMS\nXXX\n",
+            "XMASAMX\r\nSAMXMAS\r\n",
+            "XMAS\nMMXX\nAAAS\nSMXS",
+        ] {
+            assert_eq!(
+                count_with_traversals(input, &automaton).unwrap(),
+                count_occurences(input, &["XMAS"]).unwrap(),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_ragged_grid() {
+        let automaton = WordAutomaton::new(&["XMAS"]).unwrap();
+        let input = "XMAS\nXMA\nXMAS\n";
+        assert!(count_occurences(input, &["XMAS"]).is_err());
+        assert!(count_with_traversals(input, &automaton).is_err());
+    }
+
+    #[test]
+    fn test_transpose_non_square() {
+        assert_eq!(transpose("012\n345\n", 3, 2), "03\n14\n25\n");
+        assert_eq!(transpose("01\n23\n45\n", 2, 3), "024\n135\n");
+    }
+
+    #[test]
+    fn test_get_diagonals_non_square() {
+        assert_eq!(get_diagonals("0123\n4567\n", 4, 2), "0\n41\n52\n63\n7\n");
+        assert_eq!(
+            get_diagonals("01\n23\n45\n67\n", 2, 4),
+            "0\n21\n43\n65\n7\n"
+        );
+    }
+
+    #[test]
+    fn test_get_anti_diagonals_non_square() {
+        assert_eq!(
+            get_anti_diagonals("0123\n4567\n", 4, 2),
+            "4\n05\n16\n27\n3\n"
+        );
+        assert_eq!(
+            get_anti_diagonals("01\n23\n45\n67\n", 2, 4),
+            "6\n47\n25\n03\n1\n"
+        );
     }
 
     #[test]
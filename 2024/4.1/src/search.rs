@@ -0,0 +1,335 @@
+use anyhow::{bail, Result};
+
+use crate::automaton::WordAutomaton;
+
+/// The eight directions a word can be read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+    Up,
+    UpRight,
+}
+
+impl Direction {
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Right => Self::Left,
+            Self::DownRight => Self::UpLeft,
+            Self::Down => Self::Up,
+            Self::DownLeft => Self::UpRight,
+            Self::Left => Self::Right,
+            Self::UpLeft => Self::DownRight,
+            Self::Up => Self::Down,
+            Self::UpRight => Self::DownLeft,
+        }
+    }
+
+    /// The change in row and column for a single step.
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Self::Right => (0, 1),
+            Self::DownRight => (1, 1),
+            Self::Down => (1, 0),
+            Self::DownLeft => (1, -1),
+            Self::Left => (0, -1),
+            Self::UpLeft => (-1, -1),
+            Self::Up => (-1, 0),
+            Self::UpRight => (-1, 1),
+        }
+    }
+}
+
+/// A word found in the grid, starting at `row` and `col`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'w> {
+    pub row: usize,
+    pub col: usize,
+    pub direction: Direction,
+    pub word: &'w str,
+}
+
+/// A straight line through the grid from edge to edge, starting at `row` and
+/// `col`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub row: usize,
+    pub col: usize,
+    pub direction: Direction,
+    pub len: usize,
+}
+
+impl Line {
+    /// The row and column of the `i`th cell on the line.
+    pub fn cell(&self, i: usize) -> (usize, usize) {
+        let (d_row, d_col) = self.direction.delta();
+        let i = i as isize;
+        (
+            self.row.wrapping_add_signed(d_row * i),
+            self.col.wrapping_add_signed(d_col * i),
+        )
+    }
+}
+
+/// A letter grid backed by the lines of the input.
+pub struct Grid<'a> {
+    lines: Vec<&'a [u8]>,
+}
+
+/// Splits a grid into its rows, making sure all of them are equally long.
+///
+/// Accepts `\n` as well as `\r\n` line endings, with or without a final one.
+pub fn parse_rows(input: &str) -> Result<Vec<&str>> {
+    let rows: Vec<_> = input.lines().collect();
+    let Some(first) = rows.first() else {
+        bail!("The grid is empty");
+    };
+    for (i, row) in rows.iter().enumerate() {
+        if row.len() != first.len() {
+            bail!(
+                "Row {} has {} columns, but row 1 has {}",
+                i + 1,
+                row.len(),
+                first.len()
+            );
+        }
+    }
+    Ok(rows)
+}
+
+impl<'a> Grid<'a> {
+    pub fn from_str(input: &'a str) -> Result<Self> {
+        Ok(Self {
+            lines: parse_rows(input)?.into_iter().map(str::as_bytes).collect(),
+        })
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+        self.lines.get(row)?.get(col).copied()
+    }
+
+    pub fn nr_rows(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn nr_cols(&self) -> usize {
+        self.lines[0].len()
+    }
+
+    /// Every row, column, diagonal and anti-diagonal, read in one direction.
+    ///
+    /// Together with their reversals, these cover all eight directions.
+    pub fn grid_lines(&self) -> Vec<Line> {
+        let (nr_rows, nr_cols) = (self.nr_rows(), self.nr_cols());
+        let line = |row, col, direction, len| Line {
+            row,
+            col,
+            direction,
+            len,
+        };
+
+        let mut lines = Vec::with_capacity(3 * (nr_rows + nr_cols));
+        for row in 0..nr_rows {
+            lines.push(line(row, 0, Direction::Right, nr_cols));
+        }
+        for col in 0..nr_cols {
+            lines.push(line(0, col, Direction::Down, nr_rows));
+        }
+        // Diagonals start on the left or top edge, anti-diagonals on the left
+        // or bottom edge
+        for row in 0..nr_rows {
+            lines.push(line(
+                row,
+                0,
+                Direction::DownRight,
+                (nr_rows - row).min(nr_cols),
+            ));
+            lines.push(line(row, 0, Direction::UpRight, (row + 1).min(nr_cols)));
+        }
+        for col in 1..nr_cols {
+            let len = nr_rows.min(nr_cols - col);
+            lines.push(line(0, col, Direction::DownRight, len));
+            lines.push(line(nr_rows - 1, col, Direction::UpRight, len));
+        }
+        lines
+    }
+
+    /// Finds every occurrence of the words in all eight directions.
+    ///
+    /// This builds a [`WordAutomaton`] and passes over every line of the
+    /// grid once.
+    pub fn find_words<'w>(&self, words: &[&'w str]) -> Result<Vec<Match<'w>>> {
+        Ok(WordAutomaton::new(words)?.find(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTIONS: [Direction; 8] = [
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+        Direction::Up,
+        Direction::UpRight,
+    ];
+
+    #[test]
+    fn test_find_words() {
+        let grid = Grid::from_str("CAT\nXAX\nTAC\n").unwrap();
+        let matches = grid.find_words(&["CAT", "AA"]).unwrap();
+        assert_eq!(
+            matches,
+            vec![
+                Match {
+                    row: 0,
+                    col: 0,
+                    direction: Direction::Right,
+                    word: "CAT"
+                },
+                Match {
+                    row: 0,
+                    col: 1,
+                    direction: Direction::Down,
+                    word: "AA"
+                },
+                Match {
+                    row: 1,
+                    col: 1,
+                    direction: Direction::Down,
+                    word: "AA"
+                },
+                Match {
+                    row: 1,
+                    col: 1,
+                    direction: Direction::Up,
+                    word: "AA"
+                },
+                Match {
+                    row: 2,
+                    col: 1,
+                    direction: Direction::Up,
+                    word: "AA"
+                },
+                Match {
+                    row: 2,
+                    col: 2,
+                    direction: Direction::Left,
+                    word: "CAT"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_words_all_directions() {
+        let grid = Grid::from_str("S..S..S\n.A.A.A.\n..MMM..\nSAMXMAS\n..MMM..\n.A.A.A.\nS..S..S")
+            .unwrap();
+        let mut directions: Vec<_> = grid
+            .find_words(&["XMAS"])
+            .unwrap()
+            .iter()
+            .map(|m| {
+                assert_eq!((m.row, m.col), (3, 3));
+                m.direction
+            })
+            .collect();
+        directions.sort_by_key(|d| DIRECTIONS.iter().position(|other| other == d));
+        assert_eq!(directions, DIRECTIONS);
+    }
+
+    /// Reference implementation trying every word in every direction from
+    /// every cell.
+    fn find_words_naive<'w>(grid: &Grid, words: &[&'w str]) -> Vec<Match<'w>> {
+        let has_word_at = |row: usize, col: usize, direction: Direction, word: &str| {
+            let (d_row, d_col) = direction.delta();
+            word.bytes().enumerate().all(|(i, letter)| {
+                let i = i as isize;
+                let row = row.checked_add_signed(d_row * i);
+                let col = col.checked_add_signed(d_col * i);
+                match (row, col) {
+                    (Some(row), Some(col)) => grid.get(row, col) == Some(letter),
+                    _ => false,
+                }
+            })
+        };
+
+        let mut matches = Vec::new();
+        for row in 0..grid.nr_rows() {
+            for col in 0..grid.nr_cols() {
+                for &word in words {
+                    for direction in DIRECTIONS {
+                        if has_word_at(row, col, direction, word) {
+                            matches.push(Match {
+                                row,
+                                col,
+                                direction,
+                                word,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    #[test]
+    fn test_find_words_matches_naive() {
+        let words = ["XMAS", "A", "MAM", "SAX", "XX"];
+        for input in [
+            "XMASAMX\nMAMXXAS\nAXAMSAM\nSMXMASX\n",
+            "XMA\nMAS\nAMA\nSXM\nXXS\n",
+            "XAMMAS",
+            "X\nM\nA\nS",
+        ] {
+            let grid = Grid::from_str(input).unwrap();
+            assert_eq!(
+                grid.find_words(&words).unwrap(),
+                find_words_naive(&grid, &words),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let grid = Grid::from_str("ABC\nDEF\n").unwrap();
+        let read = |line: &Line| -> String {
+            (0..line.len)
+                .map(|i| {
+                    let (row, col) = line.cell(i);
+                    grid.get(row, col).unwrap() as char
+                })
+                .collect()
+        };
+        let mut lines: Vec<_> = grid.grid_lines().iter().map(read).collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            ["A", "ABC", "AD", "AE", "BE", "BF", "C", "CF", "D", "DB", "DEF", "EC", "F"]
+        );
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        assert_eq!(parse_rows("AB\nCD\n").unwrap(), vec!["AB", "CD"]);
+        assert_eq!(parse_rows("AB\r\nCD\r\n").unwrap(), vec!["AB", "CD"]);
+        assert_eq!(parse_rows("AB\nCD").unwrap(), vec!["AB", "CD"]);
+        assert_eq!(parse_rows("ABC\nDEF").unwrap(), vec!["ABC", "DEF"]);
+
+        let err = parse_rows("ABC\nDEF\nGH\n").unwrap_err();
+        assert_eq!(err.to_string(), "Row 3 has 2 columns, but row 1 has 3");
+        assert!(parse_rows("AB\n\nCD\n").is_err());
+        assert!(parse_rows("").is_err());
+    }
+}
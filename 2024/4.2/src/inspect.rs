@@ -0,0 +1,375 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Color, Print, SetAttribute, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+
+use crate::{template::Template, Puzzle};
+
+/// The eight directions a word can be read in, as row and column steps.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// The cells covered by a single occurrence of a word or template.
+pub type Cells = Vec<(usize, usize)>;
+
+/// Returns the cells of `word` read from `row` and `col` on in the direction
+/// `(d_row, d_col)`, if it's there.
+fn line_at(
+    puzzle: &Puzzle,
+    word: &str,
+    row: usize,
+    col: usize,
+    delta: (isize, isize),
+) -> Option<Cells> {
+    let mut cells = Vec::with_capacity(word.len());
+    for (i, letter) in word.chars().enumerate() {
+        let i = i as isize;
+        let row = row.checked_add_signed(delta.0 * i)?;
+        let col = col.checked_add_signed(delta.1 * i)?;
+        if puzzle.get(row, col) != Some(letter) {
+            return None;
+        }
+        cells.push((row, col));
+    }
+    Some(cells)
+}
+
+/// Finds all straight occurrences of `word` which pass through `row` and `col`.
+pub fn lines_through(puzzle: &Puzzle, word: &str, row: usize, col: usize) -> Vec<Cells> {
+    let mut lines = Vec::new();
+    for delta in DIRECTIONS {
+        // Try every position of the word on the cursor cell
+        for i in 0..word.chars().count() as isize {
+            let start = (
+                row.checked_add_signed(-delta.0 * i),
+                col.checked_add_signed(-delta.1 * i),
+            );
+            if let (Some(start_row), Some(start_col)) = start {
+                lines.extend(line_at(puzzle, word, start_row, start_col, delta));
+            }
+        }
+    }
+    lines
+}
+
+/// Counts the straight occurrences of `word` in the whole puzzle.
+pub fn count_lines(puzzle: &Puzzle, word: &str) -> usize {
+    let Some(first) = word.chars().next() else {
+        return 0;
+    };
+    puzzle
+        .match_coords(first)
+        .map(|(row, col)| {
+            DIRECTIONS
+                .iter()
+                .filter(|&&delta| line_at(puzzle, word, row, col, delta).is_some())
+                .count()
+        })
+        .sum()
+}
+
+/// Finds the cells of every placement of the template's variants.
+pub fn template_cells(
+    puzzle: &Puzzle,
+    template: &Template,
+    rotations: bool,
+    reflections: bool,
+) -> Vec<Cells> {
+    let variants = template.variants(rotations, reflections);
+    template
+        .find(puzzle, rotations, reflections)
+        .iter()
+        .map(|placement| {
+            variants[placement.variant]
+                .letter_cells()
+                .map(|(row, col)| (placement.row + row, placement.col + col))
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Words read in a straight line, like `XMAS`.
+    Line,
+    /// Placements of the 2D template, like the X-MAS cross.
+    Template,
+}
+
+/// Interactive state of the inspector, independent of the terminal.
+pub struct Inspector<'a> {
+    puzzle: &'a Puzzle,
+    row: usize,
+    col: usize,
+    mode: Mode,
+    word: String,
+    /// The word being typed, which is already searched for while typing.
+    editing: Option<String>,
+    line_total: usize,
+    placements: Vec<Cells>,
+}
+
+impl<'a> Inspector<'a> {
+    pub fn new(puzzle: &'a Puzzle, word: &str, placements: Vec<Cells>) -> Self {
+        Self {
+            puzzle,
+            row: 0,
+            col: 0,
+            mode: Mode::Line,
+            word: word.to_string(),
+            editing: None,
+            line_total: count_lines(puzzle, word),
+            placements,
+        }
+    }
+
+    fn current_word(&self) -> &str {
+        self.editing.as_deref().unwrap_or(&self.word)
+    }
+
+    /// The occurrences passing through the cursor cell in the current mode.
+    pub fn matches(&self) -> Vec<Cells> {
+        match self.mode {
+            Mode::Line => lines_through(self.puzzle, self.current_word(), self.row, self.col),
+            Mode::Template => self
+                .placements
+                .iter()
+                .filter(|cells| cells.contains(&(self.row, self.col)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The number of occurrences in the whole puzzle in the current mode.
+    pub fn total(&self) -> usize {
+        match self.mode {
+            Mode::Line => self.line_total,
+            Mode::Template => self.placements.len(),
+        }
+    }
+
+    /// Handles a key press, returning whether the inspector should keep running.
+    pub fn handle(&mut self, key: KeyCode) -> bool {
+        if let Some(editing) = &mut self.editing {
+            match key {
+                KeyCode::Char(c) => editing.push(c.to_ascii_uppercase()),
+                KeyCode::Backspace => {
+                    editing.pop();
+                }
+                KeyCode::Enter => self.word = self.editing.take().unwrap(),
+                KeyCode::Esc => self.editing = None,
+                _ => return true,
+            }
+            self.line_total = count_lines(self.puzzle, self.current_word());
+            return true;
+        }
+
+        let (rows, cols) = (self.puzzle.rows(), self.puzzle.cols());
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(rows - 1),
+            KeyCode::Left | KeyCode::Char('h') => self.col = self.col.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => self.col = (self.col + 1).min(cols - 1),
+            KeyCode::Tab => {
+                self.mode = match self.mode {
+                    Mode::Line => Mode::Template,
+                    Mode::Template => Mode::Line,
+                }
+            }
+            KeyCode::Char('/') => {
+                self.mode = Mode::Line;
+                self.editing = Some(String::new());
+                self.line_total = 0;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn status(&self, nr_matches: usize) -> String {
+        let mode = match (self.mode, &self.editing) {
+            (Mode::Line, Some(editing)) => format!("word: {}_", editing),
+            (Mode::Line, None) => format!("word: {}", self.word),
+            (Mode::Template, _) => "template".to_string(),
+        };
+        format!(
+            "row {} col {} | {} | {} through cursor, {} total",
+            self.row,
+            self.col,
+            mode,
+            nr_matches,
+            self.total()
+        )
+    }
+
+    fn render(&self, out: &mut impl Write) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let matches = self.matches();
+
+        // Keep the cursor in the middle of the visible part of the grid
+        let visible_rows = (height as usize).saturating_sub(2).min(self.puzzle.rows());
+        let visible_cols = (width as usize).min(self.puzzle.cols());
+        let top = self
+            .row
+            .saturating_sub(visible_rows / 2)
+            .min(self.puzzle.rows() - visible_rows);
+        let left = self
+            .col
+            .saturating_sub(visible_cols / 2)
+            .min(self.puzzle.cols() - visible_cols);
+
+        queue!(out, terminal::Clear(ClearType::All))?;
+        for row in top..top + visible_rows {
+            queue!(out, cursor::MoveTo(0, (row - top) as u16))?;
+            for col in left..left + visible_cols {
+                let letter = self.puzzle.get(row, col).unwrap_or(' ');
+                if (row, col) == (self.row, self.col) {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                } else if matches.iter().any(|cells| cells.contains(&(row, col))) {
+                    queue!(
+                        out,
+                        SetForegroundColor(Color::Yellow),
+                        SetAttribute(Attribute::Bold)
+                    )?;
+                }
+                queue!(out, Print(letter), SetAttribute(Attribute::Reset))?;
+            }
+        }
+
+        queue!(
+            out,
+            cursor::MoveTo(0, visible_rows as u16),
+            Print(self.status(matches.len())),
+            cursor::MoveTo(0, visible_rows as u16 + 1),
+            SetAttribute(Attribute::Dim),
+            Print("arrows/hjkl: move, tab: toggle mode, /: search word, q: quit"),
+            SetAttribute(Attribute::Reset),
+        )?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn event_loop(&mut self, out: &mut impl Write) -> Result<()> {
+        loop {
+            self.render(out)?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Runs the inspector on the alternate screen until it's quit.
+    pub fn run(&mut self) -> Result<()> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let res = self.event_loop(&mut out);
+
+        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\n\
+                           XXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX\n";
+
+    fn example() -> Puzzle {
+        Puzzle::from_input(EXAMPLE.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_count_lines() {
+        let puzzle = example();
+        assert_eq!(count_lines(&puzzle, "XMAS"), 18);
+        assert_eq!(count_lines(&puzzle, ""), 0);
+    }
+
+    #[test]
+    fn test_lines_through() {
+        let puzzle = Puzzle::from_input("XMAS\nMM..\nA.A.\nS..S\n".to_string()).unwrap();
+        assert_eq!(
+            lines_through(&puzzle, "XMAS", 1, 1),
+            vec![vec![(0, 0), (1, 1), (2, 2), (3, 3)]]
+        );
+        assert_eq!(lines_through(&puzzle, "XMAS", 0, 0).len(), 3);
+        assert!(lines_through(&puzzle, "XMAS", 1, 3).is_empty());
+    }
+
+    #[test]
+    fn test_lines_through_agree_with_count() {
+        // Every occurrence has exactly four cells the cursor can be on
+        let puzzle = example();
+        let through: usize = (0..puzzle.rows())
+            .flat_map(|row| (0..puzzle.cols()).map(move |col| (row, col)))
+            .map(|(row, col)| lines_through(&puzzle, "XMAS", row, col).len())
+            .sum();
+        assert_eq!(through, 4 * count_lines(&puzzle, "XMAS"));
+    }
+
+    #[test]
+    fn test_inspector() {
+        let puzzle = example();
+        let template = Template::from_str(crate::X_MAS).unwrap();
+        let placements = template_cells(&puzzle, &template, true, false);
+        let mut inspector = Inspector::new(&puzzle, "XMAS", placements);
+        assert_eq!(inspector.total(), 18);
+
+        // Moving stays inside the grid
+        inspector.handle(KeyCode::Up);
+        inspector.handle(KeyCode::Left);
+        assert_eq!((inspector.row, inspector.col), (0, 0));
+        for _ in 0..20 {
+            inspector.handle(KeyCode::Down);
+        }
+        assert_eq!((inspector.row, inspector.col), (9, 0));
+
+        // The A at row 1 col 2 is the centre of an X-MAS
+        inspector.row = 1;
+        inspector.col = 2;
+        inspector.handle(KeyCode::Tab);
+        assert_eq!(inspector.total(), 9);
+        assert_eq!(inspector.matches().len(), 1);
+        inspector.handle(KeyCode::Tab);
+
+        // Typing searches live, escape goes back to the previous word
+        inspector.handle(KeyCode::Char('/'));
+        inspector.handle(KeyCode::Char('s'));
+        inspector.handle(KeyCode::Char('a'));
+        assert_eq!(inspector.current_word(), "SA");
+        assert_eq!(inspector.total(), count_lines(&puzzle, "SA"));
+        inspector.handle(KeyCode::Esc);
+        assert_eq!(inspector.current_word(), "XMAS");
+        assert_eq!(inspector.total(), 18);
+
+        inspector.handle(KeyCode::Char('/'));
+        inspector.handle(KeyCode::Char('x'));
+        inspector.handle(KeyCode::Char('m'));
+        inspector.handle(KeyCode::Enter);
+        assert_eq!(inspector.current_word(), "XM");
+        assert!(inspector.handle(KeyCode::Char('k')));
+        assert!(!inspector.handle(KeyCode::Char('q')));
+    }
+}
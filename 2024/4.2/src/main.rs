@@ -1,7 +1,16 @@
+mod inspect;
+mod template;
+
 use std::fs::{self};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use inspect::Inspector;
+use template::Template;
+
+const X_MAS: &str = "M.S/.A./M.S";
+const XMAS: &str = "XMAS";
 
+#[derive(Debug)]
 struct Puzzle {
     pub input: String,
     pub cols: usize,
@@ -9,13 +18,34 @@ struct Puzzle {
 }
 
 impl Puzzle {
-    fn from_input(input: String) -> Self {
-        let cols = input.find('\n').unwrap();
-        let rows = input.len() / (cols + 1);
-        Self { input, cols, rows }
+    /// Reads a rectangular grid of letters.
+    ///
+    /// Accepts `\n` as well as `\r\n` line endings, with or without a final
+    /// one. The rows are stored with a single `\n` after each of them.
+    fn from_input(input: String) -> Result<Self> {
+        let lines: Vec<_> = input.lines().collect();
+        let Some(first) = lines.first() else {
+            bail!("The puzzle is empty");
+        };
+        let cols = first.len();
+        if let Some(i) = lines.iter().position(|line| line.len() != cols) {
+            bail!(
+                "Row {} has {} columns, but row 1 has {}",
+                i + 1,
+                lines[i].len(),
+                cols
+            );
+        }
+
+        let rows = lines.len();
+        let input = lines.iter().flat_map(|line| [line, "\n"]).collect();
+        Ok(Self { input, cols, rows })
     }
 
     fn get(&self, row: usize, col: usize) -> Option<char> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
         self.input
             .as_bytes()
             .get(row * (self.cols + 1) + col)
@@ -38,29 +68,41 @@ impl Puzzle {
 }
 
 fn main() -> Result<()> {
-    let input = fs::read_to_string("input.txt")?;
-    let puzzle = Puzzle::from_input(input);
-
-    let coords: Vec<_> = puzzle
-        .match_coords('A')
-        .filter(|(r, c)| (1..puzzle.rows() - 1).contains(r) && (1..puzzle.cols() - 1).contains(c))
-        .collect();
-
-    let mut count = 0;
-    for (r, c) in coords {
-        let top_left = puzzle.get(r - 1, c - 1).unwrap();
-        let top_right = puzzle.get(r - 1, c + 1).unwrap();
-        let bottom_left = puzzle.get(r + 1, c - 1).unwrap();
-        let bottom_right = puzzle.get(r + 1, c + 1).unwrap();
-
-        if ((top_left == 'M' && bottom_right == 'S') || (top_left == 'S' && bottom_right == 'M'))
-            && ((top_right == 'M' && bottom_left == 'S')
-                || (top_right == 'S' && bottom_left == 'M'))
-        {
-            count += 1;
+    let mut template = None;
+    let mut rotate = false;
+    let mut reflections = false;
+    let mut inspect = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--template" => {
+                let pattern = args.next().ok_or(anyhow!("--template expects a pattern"))?;
+                template = Some(Template::from_str(&pattern)?);
+            }
+            "--rotate" => rotate = true,
+            "--reflect" => reflections = true,
+            "--inspect" => inspect = true,
+            _ => bail!("Unknown argument '{}'", arg),
         }
     }
 
+    // X-MAS is rotated by default, custom templates only with --rotate
+    let rotations = rotate || template.is_none();
+    let template = match template {
+        Some(template) => template,
+        None => Template::from_str(X_MAS)?,
+    };
+
+    let input = fs::read_to_string("input.txt")?;
+    let puzzle = Puzzle::from_input(input)?;
+
+    if inspect {
+        let placements = inspect::template_cells(&puzzle, &template, rotations, reflections);
+        return Inspector::new(&puzzle, XMAS, placements).run();
+    }
+
+    let count = template.find(&puzzle, rotations, reflections).len();
+
     println!("Count: {}", count);
 
     Ok(())
@@ -73,7 +115,7 @@ mod tests {
     #[test]
     fn test_puzzle() {
         let input = "ABC\nDEF\nGHI".to_string();
-        let puzzle = Puzzle::from_input(input);
+        let puzzle = Puzzle::from_input(input).unwrap();
 
         assert_eq!(puzzle.get(0, 0), Some('A'));
         assert_eq!(puzzle.get(0, 1), Some('B'));
@@ -84,12 +126,39 @@ mod tests {
         assert_eq!(puzzle.get(2, 0), Some('G'));
         assert_eq!(puzzle.get(2, 1), Some('H'));
         assert_eq!(puzzle.get(2, 2), Some('I'));
+        assert_eq!(puzzle.get(0, 3), None);
+        assert_eq!(puzzle.get(3, 0), None);
+        assert_eq!((puzzle.rows(), puzzle.cols()), (3, 3));
+    }
+
+    #[test]
+    fn test_non_square_puzzle() {
+        for input in ["ABCD\nEFGH\n", "ABCD\r\nEFGH\r\n", "ABCD\nEFGH"] {
+            let puzzle = Puzzle::from_input(input.to_string()).unwrap();
+            assert_eq!((puzzle.rows(), puzzle.cols()), (2, 4));
+            assert_eq!(puzzle.get(1, 3), Some('H'));
+            assert_eq!(puzzle.get(1, 4), None);
+            assert_eq!(puzzle.get(2, 0), None);
+            assert_eq!(puzzle.match_coords('G').collect::<Vec<_>>(), vec![(1, 2)]);
+        }
+
+        let puzzle = Puzzle::from_input("AB\nCD\nEF\n".to_string()).unwrap();
+        assert_eq!((puzzle.rows(), puzzle.cols()), (3, 2));
+        assert_eq!(puzzle.get(2, 1), Some('F'));
+    }
+
+    #[test]
+    fn test_ragged_puzzle() {
+        let err = Puzzle::from_input("ABC\nDEF\nGH\n".to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "Row 3 has 2 columns, but row 1 has 3");
+        assert!(Puzzle::from_input("ABC\n\nDEF\n".to_string()).is_err());
+        assert!(Puzzle::from_input(String::new()).is_err());
     }
 
     #[test]
     fn test_match_coords() {
         let input = "ABC\nDAF\nGHI".to_string();
-        let puzzle = Puzzle::from_input(input);
+        let puzzle = Puzzle::from_input(input).unwrap();
 
         let coords: Vec<_> = puzzle.match_coords('A').collect();
         assert_eq!(coords, vec![(0, 0), (1, 1)]);
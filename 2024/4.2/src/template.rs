@@ -0,0 +1,258 @@
+use anyhow::{bail, Result};
+
+use crate::Puzzle;
+
+const WILDCARD: char = '.';
+
+/// A small 2D pattern of letters, where `.` matches any letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    cells: Vec<Option<char>>,
+    rows: usize,
+    cols: usize,
+}
+
+/// A placement of a template in the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// The top left corner of the template.
+    pub row: usize,
+    pub col: usize,
+    /// Index of the matching variant, see [`Template::variants`].
+    pub variant: usize,
+}
+
+impl Template {
+    /// Parses a template from its rows separated by `/`, e.g. `M.S/.A./M.S`.
+    pub fn from_str(s: &str) -> Result<Self> {
+        let lines: Vec<_> = s.split('/').collect();
+        let cols = lines[0].chars().count();
+        if cols == 0 {
+            bail!("Template '{}' has an empty row", s);
+        }
+        if let Some(line) = lines.iter().find(|line| line.chars().count() != cols) {
+            bail!(
+                "Row '{}' of template '{}' doesn't have {} letters",
+                line,
+                s,
+                cols
+            );
+        }
+
+        let cells = lines
+            .iter()
+            .flat_map(|line| line.chars())
+            .map(|c| (c != WILDCARD).then_some(c))
+            .collect();
+
+        Ok(Self {
+            cells,
+            rows: lines.len(),
+            cols,
+        })
+    }
+
+    fn get(&self, row: usize, col: usize) -> Option<char> {
+        self.cells[row * self.cols + col]
+    }
+
+    /// Rotates the template by 90 degrees clockwise.
+    pub fn rotated(&self) -> Self {
+        let cells = (0..self.cols)
+            .flat_map(|row| (0..self.rows).map(move |col| (row, col)))
+            .map(|(row, col)| self.get(self.rows - 1 - col, row))
+            .collect();
+
+        Self {
+            cells,
+            rows: self.cols,
+            cols: self.rows,
+        }
+    }
+
+    /// Mirrors the template along its vertical axis.
+    pub fn reflected(&self) -> Self {
+        let cells = (0..self.rows)
+            .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+            .map(|(row, col)| self.get(row, self.cols - 1 - col))
+            .collect();
+
+        Self {
+            cells,
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Returns the distinct variants of the template under the requested
+    /// transformations, starting with the template itself.
+    ///
+    /// Variants that look the same are only returned once, so a symmetric
+    /// shape isn't counted multiple times at the same spot.
+    pub fn variants(&self, rotations: bool, reflections: bool) -> Vec<Self> {
+        let mut variants: Vec<Self> = vec![];
+        let mut add = |template: Self| {
+            if !variants.contains(&template) {
+                variants.push(template);
+            }
+        };
+
+        let mirrors = if reflections {
+            vec![self.clone(), self.reflected()]
+        } else {
+            vec![self.clone()]
+        };
+        for mut template in mirrors {
+            let nr_rotations = if rotations { 4 } else { 1 };
+            for _ in 0..nr_rotations {
+                let next = template.rotated();
+                add(template);
+                template = next;
+            }
+        }
+
+        variants
+    }
+
+    fn matches_at(&self, puzzle: &Puzzle, row: usize, col: usize) -> bool {
+        (0..self.rows).all(|r| {
+            (0..self.cols).all(|c| match self.get(r, c) {
+                Some(letter) => puzzle.get(row + r, col + c) == Some(letter),
+                None => true,
+            })
+        })
+    }
+
+    /// The positions of the non-wildcard cells, relative to the top left corner.
+    pub fn letter_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.is_some())
+            .map(|(i, _)| (i / self.cols, i % self.cols))
+    }
+
+    /// The position and letter of the first non-wildcard cell.
+    fn anchor(&self) -> Option<(usize, usize, char)> {
+        self.cells
+            .iter()
+            .position(Option::is_some)
+            .map(|i| (i / self.cols, i % self.cols, self.cells[i].unwrap()))
+    }
+
+    /// Finds all placements of the template's variants in the puzzle.
+    pub fn find(&self, puzzle: &Puzzle, rotations: bool, reflections: bool) -> Vec<Placement> {
+        let variants = self.variants(rotations, reflections);
+
+        let mut placements = Vec::new();
+        for (variant, template) in variants.iter().enumerate() {
+            if template.rows > puzzle.rows() || template.cols > puzzle.cols() {
+                continue;
+            }
+            let max_row = puzzle.rows() - template.rows;
+            let max_col = puzzle.cols() - template.cols;
+
+            // Only try the placements which put the anchor onto its letter
+            let candidates: Vec<_> = match template.anchor() {
+                Some((anchor_row, anchor_col, letter)) => puzzle
+                    .match_coords(letter)
+                    .filter_map(|(row, col)| {
+                        Some((row.checked_sub(anchor_row)?, col.checked_sub(anchor_col)?))
+                    })
+                    .filter(|&(row, col)| row <= max_row && col <= max_col)
+                    .collect(),
+                None => (0..=max_row)
+                    .flat_map(|row| (0..=max_col).map(move |col| (row, col)))
+                    .collect(),
+            };
+
+            for (row, col) in candidates {
+                if template.matches_at(puzzle, row, col) {
+                    placements.push(Placement { row, col, variant });
+                }
+            }
+        }
+        placements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let template = Template::from_str("M.S/.A./M.S").unwrap();
+        assert_eq!((template.rows, template.cols), (3, 3));
+        assert_eq!(template.get(0, 0), Some('M'));
+        assert_eq!(template.get(0, 1), None);
+        assert_eq!(template.get(2, 2), Some('S'));
+
+        assert!(Template::from_str("M.S/.A/M.S").is_err());
+        assert!(Template::from_str("").is_err());
+    }
+
+    #[test]
+    fn test_transformations() {
+        let template = Template::from_str("AB/CD/EF").unwrap();
+        assert_eq!(template.rotated(), Template::from_str("ECA/FDB").unwrap());
+        assert_eq!(
+            template.reflected(),
+            Template::from_str("BA/DC/FE").unwrap()
+        );
+        assert_eq!(template.rotated().rotated().rotated().rotated(), template);
+    }
+
+    #[test]
+    fn test_variants() {
+        let x_mas = Template::from_str("M.S/.A./M.S").unwrap();
+        assert_eq!(
+            x_mas.variants(true, false),
+            vec![
+                x_mas.clone(),
+                Template::from_str("M.M/.A./S.S").unwrap(),
+                Template::from_str("S.M/.A./S.M").unwrap(),
+                Template::from_str("S.S/.A./M.M").unwrap(),
+            ]
+        );
+        // Reflections of the X-MAS are rotations of it as well
+        assert_eq!(x_mas.variants(true, true).len(), 4);
+        assert_eq!(x_mas.variants(false, true).len(), 2);
+        assert_eq!(x_mas.variants(false, false), vec![x_mas]);
+
+        let symmetric = Template::from_str("A.A/.A./A.A").unwrap();
+        assert_eq!(symmetric.variants(true, true).len(), 1);
+    }
+
+    #[test]
+    fn test_find() {
+        let puzzle = Puzzle::from_input(
+            ".M.S......\n..A..MSMS.\n.M.S.MAA..\n..A.ASMSM.\n.M.S.M....\n\
+             ..........\nS.S.S.S.S.\n.A.A.A.A..\nM.M.M.M.M.\n..........\n"
+                .to_string(),
+        )
+        .unwrap();
+        let x_mas = Template::from_str("M.S/.A./M.S").unwrap();
+        assert_eq!(x_mas.find(&puzzle, true, false).len(), 9);
+        assert_eq!(x_mas.find(&puzzle, false, false).len(), 2);
+        assert_eq!(
+            Template::from_str("../..")
+                .unwrap()
+                .find(&puzzle, false, false)
+                .len(),
+            81
+        );
+
+        let placements = Template::from_str("MSMS")
+            .unwrap()
+            .find(&puzzle, false, false);
+        assert_eq!(
+            placements,
+            vec![Placement {
+                row: 1,
+                col: 5,
+                variant: 0
+            }]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use crate::{Rule, RuleSet, Update};
+
+/// An update whose correctness changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flip {
+    /// Index of the update, starting at 0.
+    pub update: usize,
+    /// Whether the update is correctly ordered now.
+    pub correct: bool,
+}
+
+/// Keeps track of which updates are correctly ordered while rules are added
+/// and removed.
+///
+/// Every update knows how many rules it breaks. A rule only concerns the
+/// updates containing both of its pages, which are found through an index
+/// from page to updates, so a change never looks at any other update.
+pub struct Validator {
+    ruleset: RuleSet,
+    positions: Vec<HashMap<u32, usize>>,
+    page_to_updates: HashMap<u32, Vec<usize>>,
+    nr_violations: Vec<usize>,
+}
+
+impl Validator {
+    pub fn new(ruleset: RuleSet, updates: &[Update]) -> Self {
+        let positions: Vec<HashMap<u32, usize>> = updates
+            .iter()
+            .map(|update| {
+                update
+                    .pages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &page)| (page, i))
+                    .collect()
+            })
+            .collect();
+
+        let mut page_to_updates: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, update) in updates.iter().enumerate() {
+            for &page in &update.pages {
+                page_to_updates.entry(page).or_default().push(i);
+            }
+        }
+
+        let mut validator = Self {
+            ruleset: RuleSet::new(Vec::new()),
+            nr_violations: vec![0; updates.len()],
+            positions,
+            page_to_updates,
+        };
+        for &rule in &ruleset.rules {
+            validator.insert(rule);
+        }
+        validator
+    }
+
+    /// The updates containing both pages of the rule, with whether they break
+    /// it. Just like when checking every rule, a rule with the same
+    /// page twice is broken by every update containing that page.
+    fn affected(&self, rule: Rule) -> Vec<(usize, bool)> {
+        let (Some(first), Some(second)) = (
+            self.page_to_updates.get(&rule.first),
+            self.page_to_updates.get(&rule.second),
+        ) else {
+            return Vec::new();
+        };
+        let candidates = if first.len() < second.len() {
+            first
+        } else {
+            second
+        };
+
+        candidates
+            .iter()
+            .filter_map(|&i| {
+                let positions = &self.positions[i];
+                let first = positions.get(&rule.first)?;
+                let second = positions.get(&rule.second)?;
+                Some((i, first >= second))
+            })
+            .collect()
+    }
+
+    /// Adds a rule, returning the updates which aren't correct anymore.
+    pub fn insert(&mut self, rule: Rule) -> Vec<Flip> {
+        if !self.ruleset.insert(rule) {
+            return Vec::new();
+        }
+
+        let mut flips = Vec::new();
+        for (update, broken) in self.affected(rule) {
+            if broken {
+                self.nr_violations[update] += 1;
+                if self.nr_violations[update] == 1 {
+                    flips.push(Flip {
+                        update,
+                        correct: false,
+                    });
+                }
+            }
+        }
+        flips
+    }
+
+    /// Removes a rule, returning the updates which are correct now.
+    pub fn remove(&mut self, rule: Rule) -> Vec<Flip> {
+        if !self.ruleset.remove(rule) {
+            return Vec::new();
+        }
+
+        let mut flips = Vec::new();
+        for (update, broken) in self.affected(rule) {
+            if broken {
+                self.nr_violations[update] -= 1;
+                if self.nr_violations[update] == 0 {
+                    flips.push(Flip {
+                        update,
+                        correct: true,
+                    });
+                }
+            }
+        }
+        flips
+    }
+
+    pub fn is_correct(&self, update: usize) -> bool {
+        self.nr_violations[update] == 0
+    }
+
+    pub fn nr_updates(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn nr_correct(&self) -> usize {
+        (0..self.nr_updates())
+            .filter(|&i| self.is_correct(i))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::precedence::PrecedenceTable;
+
+    #[test]
+    fn test_insert_remove() {
+        let updates = vec![
+            Update::from_str("1,2,3").unwrap(),
+            Update::from_str("3,2,1").unwrap(),
+            Update::from_str("4,5").unwrap(),
+        ];
+        let mut validator = Validator::new(RuleSet::from_str("1|2").unwrap(), &updates);
+        assert_eq!(validator.nr_correct(), 2);
+        assert!(!validator.is_correct(1));
+
+        // The second update is already incorrect, so nothing flips
+        assert_eq!(validator.insert(Rule::new(2, 3)), vec![]);
+        assert_eq!(validator.insert(Rule::new(3, 4)), vec![]);
+        assert_eq!(
+            validator.insert(Rule::new(3, 1)),
+            vec![Flip {
+                update: 0,
+                correct: false
+            }]
+        );
+        // Inserting a rule twice doesn't count it twice
+        assert_eq!(validator.insert(Rule::new(3, 1)), vec![]);
+
+        assert_eq!(validator.remove(Rule::new(1, 2)), vec![]);
+        assert_eq!(
+            validator.remove(Rule::new(2, 3)),
+            vec![Flip {
+                update: 1,
+                correct: true
+            }]
+        );
+        assert_eq!(
+            validator.remove(Rule::new(3, 1)),
+            vec![Flip {
+                update: 0,
+                correct: true
+            }]
+        );
+        assert_eq!(validator.remove(Rule::new(3, 1)), vec![]);
+        assert_eq!(validator.nr_correct(), 3);
+    }
+
+    #[test]
+    fn test_agrees_with_full_check() {
+        let (ruleset, updates) = crate::parser::load_input();
+        let mut rules = ruleset.rules.clone();
+        let mut validator = Validator::new(ruleset, &updates);
+
+        let status = |rules: &[Rule]| -> Vec<bool> {
+            // Like the full check, a self-rule breaks every update with its page
+            let table = PrecedenceTable::new(&RuleSet::new(rules.to_vec()));
+            updates
+                .iter()
+                .map(|update| {
+                    table.is_correctly_ordered(&update.pages)
+                        && update.pages.iter().all(|&page| !table.precedes(page, page))
+                })
+                .collect()
+        };
+        let mut before = status(&rules);
+
+        // Alternate between removing a rule and inserting one, spreading both
+        // over the whole range of pages. At i = 45 both pages are the same.
+        for i in 0..60 {
+            let flips = if i % 2 == 0 && !rules.is_empty() {
+                let rule = rules.remove(i * 31 % rules.len());
+                validator.remove(rule)
+            } else {
+                let rule = Rule::new(10 + i as u32 * 37 % 90, 10 + i as u32 * 53 % 90);
+                if !rules.contains(&rule) {
+                    rules.push(rule);
+                }
+                validator.insert(rule)
+            };
+
+            let after = status(&rules);
+            let expected: Vec<_> = (0..after.len())
+                .filter(|&i| before[i] != after[i])
+                .map(|i| Flip {
+                    update: i,
+                    correct: after[i],
+                })
+                .collect();
+            let mut flips = flips;
+            flips.sort_by_key(|flip| flip.update);
+            assert_eq!(flips, expected);
+            before = after;
+        }
+    }
+}
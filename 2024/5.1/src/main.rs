@@ -1,11 +1,19 @@
+mod incremental;
+mod parser;
+mod precedence;
+
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap},
     fs::{self},
-    num::ParseIntError,
+    str::FromStr,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use incremental::Validator;
+use parser::ParseError;
+use precedence::PrecedenceTable;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Rule {
     first: u32,
     second: u32,
@@ -15,30 +23,63 @@ impl Rule {
     fn new(first: u32, second: u32) -> Self {
         Self { first, second }
     }
+}
 
-    fn from_str(s: &str) -> Result<Self> {
-        let mut parts = s.split('|');
-        let first = parts.next().unwrap().parse()?;
-        let second = parts.next().unwrap().parse()?;
+impl FromStr for Rule {
+    type Err = ParseError;
 
-        Ok(Self::new(first, second))
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_rule(1, s)
     }
 }
 
 struct RuleSet {
     rules: Vec<Rule>,
+    /// The position of every rule in `rules`, so rules can be looked up,
+    /// added and removed in constant time.
+    positions: HashMap<Rule, usize>,
 }
 
 impl RuleSet {
     fn new(rules: Vec<Rule>) -> Self {
-        Self { rules }
+        let mut ruleset = Self {
+            rules: Vec::with_capacity(rules.len()),
+            positions: HashMap::with_capacity(rules.len()),
+        };
+        for rule in rules {
+            ruleset.insert(rule);
+        }
+        ruleset
+    }
+
+    /// Adds the rule, returning whether it wasn't there yet.
+    fn insert(&mut self, rule: Rule) -> bool {
+        match self.positions.entry(rule) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(self.rules.len());
+                self.rules.push(rule);
+                true
+            }
+        }
     }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let rules = s.lines().map(Rule::from_str).collect::<Result<Vec<_>>>()?;
-        Ok(Self::new(rules))
+    /// Removes the rule, returning whether it was there.
+    ///
+    /// The last rule takes the place of the removed one.
+    fn remove(&mut self, rule: Rule) -> bool {
+        let Some(i) = self.positions.remove(&rule) else {
+            return false;
+        };
+        self.rules.swap_remove(i);
+        if let Some(&moved) = self.rules.get(i) {
+            self.positions.insert(moved, i);
+        }
+        true
     }
 
+    /// Checks every rule, see [`PrecedenceTable`] for the fast way.
+    #[cfg(test)]
     fn is_correctly_ordered(&self, update: &Update) -> bool {
         let update_pages: HashMap<u32, usize> = HashMap::from_iter(
             update
@@ -64,6 +105,16 @@ impl RuleSet {
     }
 }
 
+impl FromStr for RuleSet {
+    type Err = ParseError;
+
+    /// Parses one rule per line.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_rules(s.lines().enumerate().map(|(i, line)| (i + 1, line)))
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Update {
     pages: Vec<u32>,
 }
@@ -73,36 +124,73 @@ impl Update {
         Self { pages }
     }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let pages = s
-            .split(',')
-            .map(str::parse)
-            .collect::<Result<Vec<_>, ParseIntError>>()?;
-        Ok(Self::new(pages))
-    }
-
     fn middle_page(&self) -> u32 {
         let mid = self.pages.len() / 2;
         self.pages[mid]
     }
 }
 
+impl FromStr for Update {
+    type Err = ParseError;
+
+    /// Unlike in the puzzle input, updates of even length are accepted.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_update(1, s, false)
+    }
+}
+
+/// Applies rule edits, one per line as `+a|b` or `-a|b`, printing the updates
+/// whose correctness changes with each of them.
+fn apply_edits(ruleset: RuleSet, updates: Vec<Update>, edits: &str) -> Result<()> {
+    let mut validator = Validator::new(ruleset, &updates);
+    for (i, line) in edits.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let flips = if let Some(rule) = line.strip_prefix('+') {
+            validator.insert(parser::parse_rule(i + 1, rule)?)
+        } else if let Some(rule) = line.strip_prefix('-') {
+            validator.remove(parser::parse_rule(i + 1, rule)?)
+        } else {
+            bail!("Line {}: edits have to start with + or -", i + 1);
+        };
+
+        println!("{}", line);
+        for flip in flips {
+            let status = if flip.correct { "correct" } else { "incorrect" };
+            println!("  update {} is now {}", flip.update + 1, status);
+        }
+    }
+
+    println!(
+        "{} out of {} updates are correctly ordered",
+        validator.nr_correct(),
+        validator.nr_updates()
+    );
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let input = fs::read_to_string("input.txt")?;
-    let (rules, updates) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Couldn't find blank line!"))?;
-
-    let ruleset = RuleSet::from_str(rules)?;
+    let (ruleset, updates) = parser::parse(&input, true)?;
+    let table = PrecedenceTable::new(&ruleset);
 
-    let updates = updates
-        .lines()
-        .map(Update::from_str)
-        .collect::<Result<Vec<_>>>()?;
+    let mut args = std::env::args().skip(1);
+    if let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--edits" => {
+                let path = args.next().ok_or(anyhow!("--edits expects a file path"))?;
+                return apply_edits(ruleset, updates, &fs::read_to_string(path)?);
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
 
     let correctly_ordered_updates: Vec<_> = updates
         .iter()
-        .filter(|update| ruleset.is_correctly_ordered(update))
+        .filter(|update| table.is_correctly_ordered(&update.pages))
         .collect();
 
     println!(
@@ -0,0 +1,241 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::{Rule, RuleSet, Update};
+
+/// A problem with the puzzle input, with the number of the line it's on,
+/// starting at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// There's no blank line between the rules and the updates.
+    MissingSeparator,
+    WrongFieldCount {
+        line: usize,
+        count: usize,
+    },
+    InvalidPage {
+        line: usize,
+        page: String,
+    },
+    DuplicateRule {
+        line: usize,
+        first_line: usize,
+    },
+    SelfRule {
+        line: usize,
+        page: u32,
+    },
+    DuplicatePage {
+        line: usize,
+        page: u32,
+    },
+    /// The update has no single middle page.
+    EvenLength {
+        line: usize,
+        len: usize,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSeparator => {
+                write!(f, "No blank line between the rules and the updates")
+            }
+            Self::WrongFieldCount { line, count } => write!(
+                f,
+                "Line {}: a rule needs exactly 2 pages separated by '|', found {}",
+                line, count
+            ),
+            Self::InvalidPage { line, page } => {
+                write!(f, "Line {}: '{}' isn't a valid page", line, page)
+            }
+            Self::DuplicateRule { line, first_line } => write!(
+                f,
+                "Line {}: the rule was already given on line {}",
+                line, first_line
+            ),
+            Self::SelfRule { line, page } => {
+                write!(f, "Line {}: page {} can't come before itself", line, page)
+            }
+            Self::DuplicatePage { line, page } => {
+                write!(f, "Line {}: page {} appears more than once", line, page)
+            }
+            Self::EvenLength { line, len } => write!(
+                f,
+                "Line {}: the update has {} pages, so there's no middle page",
+                line, len
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+fn parse_page(line: usize, page: &str) -> Result<u32, ParseError> {
+    page.parse().map_err(|_| ParseError::InvalidPage {
+        line,
+        page: page.to_string(),
+    })
+}
+
+/// Parses a single rule like `47|53`.
+pub fn parse_rule(line: usize, s: &str) -> Result<Rule, ParseError> {
+    let fields: Vec<_> = s.split('|').collect();
+    let [first, second] = fields[..] else {
+        return Err(ParseError::WrongFieldCount {
+            line,
+            count: fields.len(),
+        });
+    };
+    let first = parse_page(line, first)?;
+    let second = parse_page(line, second)?;
+    if first == second {
+        return Err(ParseError::SelfRule { line, page: first });
+    }
+    Ok(Rule::new(first, second))
+}
+
+/// Parses numbered lines which all hold a rule.
+pub fn parse_rules<'a>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<RuleSet, ParseError> {
+    let mut rules = Vec::new();
+    let mut rule_lines = HashMap::new();
+    for (line, s) in lines {
+        let rule = parse_rule(line, s)?;
+        if let Some(&first_line) = rule_lines.get(&(rule.first, rule.second)) {
+            return Err(ParseError::DuplicateRule { line, first_line });
+        }
+        rule_lines.insert((rule.first, rule.second), line);
+        rules.push(rule);
+    }
+    Ok(RuleSet::new(rules))
+}
+
+/// Parses an update like `75,47,61,53,29`.
+///
+/// With `odd`, the update has to have an odd number of pages, so its middle
+/// page can be taken.
+pub fn parse_update(line: usize, s: &str, odd: bool) -> Result<Update, ParseError> {
+    let mut seen = HashSet::new();
+    let mut pages = Vec::new();
+    for page in s.split(',') {
+        let page = parse_page(line, page)?;
+        if !seen.insert(page) {
+            return Err(ParseError::DuplicatePage { line, page });
+        }
+        pages.push(page);
+    }
+    if odd && pages.len() % 2 == 0 {
+        return Err(ParseError::EvenLength {
+            line,
+            len: pages.len(),
+        });
+    }
+    Ok(Update::new(pages))
+}
+
+/// Parses the rules and updates, accepting `\n` as well as `\r\n` line
+/// endings.
+///
+/// With `odd_updates`, every update has to have an odd number of pages.
+pub fn parse(input: &str, odd_updates: bool) -> Result<(RuleSet, Vec<Update>), ParseError> {
+    let lines: Vec<_> = input.lines().enumerate().map(|(i, s)| (i + 1, s)).collect();
+    let separator = lines
+        .iter()
+        .position(|(_, s)| s.is_empty())
+        .ok_or(ParseError::MissingSeparator)?;
+
+    let ruleset = parse_rules(lines[..separator].iter().copied())?;
+    let updates = lines[separator + 1..]
+        .iter()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|&(line, s)| parse_update(line, s, odd_updates))
+        .collect::<Result<_, _>>()?;
+
+    Ok((ruleset, updates))
+}
+
+/// Parses the puzzle input, for the tests which check against the real data.
+#[cfg(test)]
+pub fn load_input() -> (RuleSet, Vec<Update>) {
+    let input = std::fs::read_to_string("input.txt").unwrap();
+    parse(&input, true).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> ParseError {
+        parse(input, true).err().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let (ruleset, updates) = parse("1|2\n3|4\n\n1,2,3\n4,3,5\n", true).unwrap();
+        assert_eq!(ruleset.rules, vec![Rule::new(1, 2), Rule::new(3, 4)]);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1].pages, vec![4, 3, 5]);
+
+        let (ruleset, updates) = parse("1|2\r\n3|4\r\n\r\n1,2,3\r\n4,3,5", true).unwrap();
+        assert_eq!(ruleset.rules.len(), 2);
+        assert_eq!(updates[1].pages, vec![4, 3, 5]);
+
+        assert!(parse("1|2\n\n1,2\n", false).is_ok());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(error("1|2\n1,2,3\n"), ParseError::MissingSeparator);
+        assert_eq!(
+            error("1|2\n12\n\n1,2,3\n"),
+            ParseError::WrongFieldCount { line: 2, count: 1 }
+        );
+        assert_eq!(
+            error("1|2|3\n\n1,2,3\n"),
+            ParseError::WrongFieldCount { line: 1, count: 3 }
+        );
+        assert_eq!(
+            error("1|x\n\n1,2,3\n"),
+            ParseError::InvalidPage {
+                line: 1,
+                page: "x".to_string()
+            }
+        );
+        assert_eq!(
+            error("1|2\n3|4\n1|2\n\n1,2,3\n"),
+            ParseError::DuplicateRule {
+                line: 3,
+                first_line: 1
+            }
+        );
+        assert_eq!(
+            error("1|2\n3|3\n\n1,2,3\n"),
+            ParseError::SelfRule { line: 2, page: 3 }
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,3\n1,2,1\n"),
+            ParseError::DuplicatePage { line: 4, page: 1 }
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,3\n1,2\n"),
+            ParseError::EvenLength { line: 4, len: 2 }
+        );
+        assert_eq!(
+            error("1|2\n\n1,,3\n"),
+            ParseError::InvalidPage {
+                line: 3,
+                page: String::new()
+            }
+        );
+        assert_eq!(
+            error("1|2\n12\n\n1,2,3\n").to_string(),
+            "Line 2: a rule needs exactly 2 pages separated by '|', found 1"
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use crate::RuleSet;
+
+/// All the rules compiled into a set of ordered page pairs, to look up
+/// whether one page has to come before another in constant time.
+#[derive(Debug, Clone)]
+pub struct PrecedenceTable {
+    pairs: HashSet<(u32, u32)>,
+}
+
+impl PrecedenceTable {
+    pub fn new(rules: &RuleSet) -> Self {
+        Self {
+            pairs: rules
+                .rules
+                .iter()
+                .map(|rule| (rule.first, rule.second))
+                .collect(),
+        }
+    }
+
+    /// Whether a rule says that `first` has to come before `second`.
+    #[inline]
+    pub fn precedes(&self, first: u32, second: u32) -> bool {
+        self.pairs.contains(&(first, second))
+    }
+
+    /// Checks that no page has to come before any page preceding it.
+    ///
+    /// Every pair of pages is looked up, as the rules don't have to relate
+    /// neighbouring pages: with only `1|3`, `3,2,1` is out of order.
+    pub fn is_correctly_ordered(&self, pages: &[u32]) -> bool {
+        pages.iter().enumerate().all(|(i, &first)| {
+            pages[i + 1..]
+                .iter()
+                .all(|&second| !self.precedes(second, first))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_precedes() {
+        let table = PrecedenceTable::new(&RuleSet::from_str("1|2\n3|4").unwrap());
+        assert!(table.precedes(1, 2));
+        assert!(!table.precedes(2, 1));
+        assert!(!table.precedes(1, 3));
+        assert!(table.is_correctly_ordered(&[1, 2, 3, 4]));
+        assert!(!table.is_correctly_ordered(&[1, 4, 3, 2]));
+    }
+
+    #[test]
+    fn test_rule_between_distant_pages() {
+        let table = PrecedenceTable::new(&RuleSet::from_str("1|3").unwrap());
+        assert!(table.is_correctly_ordered(&[1, 2, 3]));
+        assert!(!table.is_correctly_ordered(&[3, 2, 1]));
+    }
+
+    #[test]
+    fn test_agrees_with_rules() {
+        let (ruleset, updates) = crate::parser::load_input();
+        let table = PrecedenceTable::new(&ruleset);
+
+        for update in &updates {
+            assert_eq!(
+                table.is_correctly_ordered(&update.pages),
+                ruleset.is_correctly_ordered(update)
+            );
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{RuleSet, Update};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Dot,
+    Mermaid,
+}
+
+impl ExportFormat {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            _ => bail!("Unknown export format '{}', expected dot or mermaid", s),
+        }
+    }
+}
+
+/// Assigns every page its topological layer: pages without a rule putting
+/// anything before them are in layer 0, all others are one layer behind the
+/// last page they have to follow.
+///
+/// Pages which are part of, or come after, a cycle don't get a layer.
+pub fn layers(rules: &RuleSet, pages: &BTreeSet<u32>) -> BTreeMap<u32, Option<usize>> {
+    let mut nr_predecessors: BTreeMap<u32, usize> = pages.iter().map(|&p| (p, 0)).collect();
+    let mut successors: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for rule in rules.iter() {
+        *nr_predecessors.get_mut(&rule.second).unwrap() += 1;
+        successors.entry(rule.first).or_default().push(rule.second);
+    }
+
+    let mut layers: BTreeMap<u32, Option<usize>> = pages.iter().map(|&p| (p, None)).collect();
+    let mut layer: Vec<u32> = nr_predecessors
+        .iter()
+        .filter(|(_, &n)| n == 0)
+        .map(|(&p, _)| p)
+        .collect();
+    let mut depth = 0;
+    while !layer.is_empty() {
+        let mut next_layer = Vec::new();
+        for page in layer {
+            layers.insert(page, Some(depth));
+            for &next in successors.get(&page).into_iter().flatten() {
+                let n = nr_predecessors.get_mut(&next).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    next_layer.push(next);
+                }
+            }
+        }
+        layer = next_layer;
+        depth += 1;
+    }
+    layers
+}
+
+fn label(page: u32, layer: Option<usize>) -> String {
+    match layer {
+        Some(layer) => format!("{} (layer {})", page, layer),
+        None => format!("{} (cyclic)", page),
+    }
+}
+
+/// Exports the rules as a graph, pointing from the page which has to come
+/// first to the one which has to come later.
+///
+/// With an update, only its pages and the rules between them are included,
+/// and the rules its current order breaks are highlighted in red.
+pub fn export(ruleset: &RuleSet, update: Option<&Update>, format: ExportFormat) -> String {
+    let (rules, pages): (_, BTreeSet<u32>) = match update {
+        Some(update) => (
+            ruleset.get_applicable_rules(update),
+            update.pages.iter().copied().collect(),
+        ),
+        None => (
+            ruleset.clone(),
+            ruleset
+                .iter()
+                .flat_map(|rule| [rule.first, rule.second])
+                .collect(),
+        ),
+    };
+    let layers = layers(&rules, &pages);
+    let is_violated = |first: u32, second: u32| {
+        update.is_some_and(|update| update.index_of(first) > update.index_of(second))
+    };
+
+    let mut nodes: Vec<_> = layers.iter().map(|(&page, &layer)| (layer, page)).collect();
+    nodes.sort_by_key(|&(layer, page)| (layer.is_none(), layer, page));
+
+    let mut out = String::new();
+    match format {
+        ExportFormat::Dot => {
+            out.push_str("digraph rules {\n    rankdir=LR;\n");
+            for (layer, page) in nodes {
+                writeln!(out, "    {} [label=\"{}\"];", page, label(page, layer)).unwrap();
+            }
+            for rule in rules.iter() {
+                let style = if is_violated(rule.first, rule.second) {
+                    " [color=red, penwidth=2]"
+                } else {
+                    ""
+                };
+                writeln!(out, "    {} -> {}{};", rule.first, rule.second, style).unwrap();
+            }
+            out.push_str("}\n");
+        }
+        ExportFormat::Mermaid => {
+            out.push_str("graph LR\n");
+            for (layer, page) in nodes {
+                writeln!(out, "    p{}[\"{}\"]", page, label(page, layer)).unwrap();
+            }
+            let mut violated = Vec::new();
+            for (i, rule) in rules.iter().enumerate() {
+                writeln!(out, "    p{} --> p{}", rule.first, rule.second).unwrap();
+                if is_violated(rule.first, rule.second) {
+                    violated.push(i);
+                }
+            }
+            // Mermaid styles edges by the order they were declared in
+            for i in violated {
+                writeln!(out, "    linkStyle {} stroke:red,stroke-width:2px", i).unwrap();
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn example() -> RuleSet {
+        RuleSet::from_str("47|53\n97|61\n97|47\n75|53\n61|53\n97|53\n75|47\n97|75\n47|61\n75|61")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_layers() {
+        let rules = RuleSet::from_str("1|2\n2|3\n1|3\n4|5\n5|4\n5|6").unwrap();
+        let pages = (1..=7).collect();
+        let layers = layers(&rules, &pages);
+        assert_eq!(
+            layers.into_iter().collect::<Vec<_>>(),
+            vec![
+                (1, Some(0)),
+                (2, Some(1)),
+                (3, Some(2)),
+                (4, None),
+                (5, None),
+                (6, None),
+                (7, Some(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let update = Update::from_str("61,13,29").unwrap();
+        let ruleset = RuleSet::from_str("29|13\n61|13\n61|29\n13|99").unwrap();
+        assert_eq!(
+            export(&ruleset, Some(&update), ExportFormat::Dot),
+            "digraph rules {\n    rankdir=LR;\n    \
+             61 [label=\"61 (layer 0)\"];\n    \
+             29 [label=\"29 (layer 1)\"];\n    \
+             13 [label=\"13 (layer 2)\"];\n    \
+             29 -> 13 [color=red, penwidth=2];\n    \
+             61 -> 13;\n    \
+             61 -> 29;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_export_mermaid() {
+        let ruleset = example();
+        let update = Update::from_str("75,97,47,61,53").unwrap();
+        let mermaid = export(&ruleset, Some(&update), ExportFormat::Mermaid);
+        assert!(
+            mermaid.starts_with("graph LR\n    p97[\"97 (layer 0)\"]\n    p75[\"75 (layer 1)\"]\n")
+        );
+        // 97|75 is the 8th rule, and the only one broken
+        assert!(mermaid.ends_with(
+            "    p97 --> p75\n    p47 --> p61\n    p75 --> p61\n    \
+             linkStyle 7 stroke:red,stroke-width:2px\n"
+        ));
+
+        // Without an update everything is included and nothing highlighted
+        let full = export(&ruleset, None, ExportFormat::Mermaid);
+        assert_eq!(full.lines().count(), 1 + 5 + 10);
+        assert!(!full.contains("linkStyle"));
+    }
+}
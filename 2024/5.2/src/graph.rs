@@ -0,0 +1,284 @@
+use std::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use anyhow::Result;
+
+use crate::{RuleSet, Update};
+
+/// The pages as nodes and the rules as edges, pointing from the page which
+/// has to come first to the one which has to come later.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    edges: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl Graph {
+    pub fn new(rules: &RuleSet) -> Self {
+        let mut edges: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+        for rule in rules.iter() {
+            edges.entry(rule.first).or_default().insert(rule.second);
+            edges.entry(rule.second).or_default();
+        }
+        Self { edges }
+    }
+
+    /// The subgraph consisting of the given pages and the edges between them.
+    pub fn induced(&self, pages: &[u32]) -> Self {
+        let pages: BTreeSet<_> = pages.iter().copied().collect();
+        let edges = pages
+            .iter()
+            .map(|&page| {
+                let successors = self
+                    .successors(page)
+                    .filter(|next| pages.contains(next))
+                    .collect();
+                (page, successors)
+            })
+            .collect();
+        Self { edges }
+    }
+
+    pub fn nr_nodes(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn nr_edges(&self) -> usize {
+        self.edges.values().map(BTreeSet::len).sum()
+    }
+
+    pub fn has_edge(&self, from: u32, to: u32) -> bool {
+        self.edges.get(&from).is_some_and(|next| next.contains(&to))
+    }
+
+    fn successors(&self, page: u32) -> impl Iterator<Item = u32> + '_ {
+        self.edges.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Finds the strongly connected components with Tarjan's algorithm.
+    ///
+    /// The components are returned in reverse topological order, and the
+    /// pages of each component are sorted.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<u32>> {
+        let mut tarjan = Tarjan::default();
+        for &page in self.edges.keys() {
+            if !tarjan.index.contains_key(&page) {
+                tarjan.visit(self, page);
+            }
+        }
+        tarjan.components
+    }
+
+    /// Whether the graph has no cycles.
+    pub fn is_acyclic(&self) -> bool {
+        let self_loop = self.edges.keys().any(|&page| self.has_edge(page, page));
+        !self_loop
+            && self
+                .strongly_connected_components()
+                .iter()
+                .all(|component| component.len() == 1)
+    }
+
+    /// Finds a cycle with the least number of pages, if there is any.
+    ///
+    /// Runs a breadth first search from every page, looking for the shortest
+    /// way back to it.
+    pub fn shortest_cycle(&self) -> Option<Vec<u32>> {
+        let mut shortest: Option<Vec<u32>> = None;
+        for &start in self.edges.keys() {
+            let mut parents = HashMap::from([(start, start)]);
+            let mut queue = VecDeque::from([start]);
+            let mut last = None;
+            'search: while let Some(page) = queue.pop_front() {
+                for next in self.successors(page) {
+                    if next == start {
+                        last = Some(page);
+                        break 'search;
+                    }
+                    if let Entry::Vacant(entry) = parents.entry(next) {
+                        entry.insert(page);
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            let Some(mut page) = last else {
+                continue;
+            };
+            let mut cycle = vec![page];
+            while page != start {
+                page = parents[&page];
+                cycle.push(page);
+            }
+            cycle.reverse();
+
+            if shortest.as_ref().is_none_or(|s| cycle.len() < s.len()) {
+                shortest = Some(cycle);
+            }
+        }
+        shortest
+    }
+}
+
+#[derive(Default)]
+struct Tarjan {
+    next_index: usize,
+    index: HashMap<u32, usize>,
+    low_link: HashMap<u32, usize>,
+    stack: Vec<u32>,
+    on_stack: BTreeSet<u32>,
+    components: Vec<Vec<u32>>,
+}
+
+impl Tarjan {
+    fn visit(&mut self, graph: &Graph, page: u32) {
+        self.index.insert(page, self.next_index);
+        self.low_link.insert(page, self.next_index);
+        self.next_index += 1;
+        self.stack.push(page);
+        self.on_stack.insert(page);
+
+        for next in graph.successors(page) {
+            if !self.index.contains_key(&next) {
+                self.visit(graph, next);
+                let low_link = self.low_link[&page].min(self.low_link[&next]);
+                self.low_link.insert(page, low_link);
+            } else if self.on_stack.contains(&next) {
+                let low_link = self.low_link[&page].min(self.index[&next]);
+                self.low_link.insert(page, low_link);
+            }
+        }
+
+        // The page is the root of a component, which is on top of the stack
+        if self.low_link[&page] == self.index[&page] {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack.remove(&member);
+                component.push(member);
+                if member == page {
+                    break;
+                }
+            }
+            component.sort();
+            self.components.push(component);
+        }
+    }
+}
+
+fn format_cycle(cycle: &[u32]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Prints the components of the full rule graph, and for every update
+/// whether its rules can be followed and whether that's possible in only one
+/// way.
+pub fn print_analysis(ruleset: &RuleSet, updates: &[Update]) -> Result<()> {
+    let graph = Graph::new(ruleset);
+    let components = graph.strongly_connected_components();
+    println!(
+        "Rule graph: {} pages, {} rules, {} strongly connected components",
+        graph.nr_nodes(),
+        graph.nr_edges(),
+        components.len()
+    );
+    for component in components.iter().filter(|c| c.len() > 1) {
+        println!(
+            "  cyclic component of {} pages: {:?}",
+            component.len(),
+            component
+        );
+    }
+    if let Some(cycle) = graph.shortest_cycle() {
+        println!("  shortest cycle: {}", format_cycle(&cycle));
+    }
+
+    let mut nr_unique = 0;
+    let mut nr_cyclic = 0;
+    for (i, update) in updates.iter().enumerate() {
+        let induced = graph.induced(&update.pages);
+        let verdict = if induced.is_acyclic() {
+            // A topological order is the only one iff each page has to come
+            // directly before the next one
+            let sorted = update.sort_according_to(ruleset)?;
+            let unique = sorted
+                .pages
+                .windows(2)
+                .all(|pair| induced.has_edge(pair[0], pair[1]));
+            if unique {
+                nr_unique += 1;
+                "DAG, unique ordering".to_string()
+            } else {
+                "DAG, multiple orderings".to_string()
+            }
+        } else {
+            nr_cyclic += 1;
+            let cycle = induced.shortest_cycle().unwrap();
+            format!("not a DAG, cycle {}", format_cycle(&cycle))
+        };
+        println!("Update {}: {}", i + 1, verdict);
+    }
+
+    println!(
+        "{} updates have a unique ordering, {} can be ordered in multiple ways, {} can't be ordered",
+        nr_unique,
+        updates.len() - nr_unique - nr_cyclic,
+        nr_cyclic
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::Rule;
+
+    fn graph(rules: &str) -> Graph {
+        Graph::new(&RuleSet::from_str(rules).unwrap())
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        // The parser rejects self-rules, so add it by hand
+        let mut ruleset = RuleSet::from_str("1|2\n2|3\n3|1\n3|4\n4|5\n5|4").unwrap();
+        ruleset.rules.push(Rule::new(6, 6));
+        let graph = Graph::new(&ruleset);
+        assert_eq!(
+            graph.strongly_connected_components(),
+            vec![vec![4, 5], vec![1, 2, 3], vec![6]]
+        );
+        assert!(!graph.is_acyclic());
+        assert!(!graph.induced(&[6]).is_acyclic());
+        assert!(graph.induced(&[1, 2, 4]).is_acyclic());
+    }
+
+    #[test]
+    fn test_shortest_cycle() {
+        let graph = graph("1|2\n2|3\n3|4\n4|1\n3|1");
+        assert_eq!(graph.shortest_cycle(), Some(vec![1, 2, 3]));
+        assert_eq!(graph.induced(&[1, 2, 4]).shortest_cycle(), None);
+        assert_eq!(format_cycle(&[1, 2, 3]), "1 -> 2 -> 3 -> 1");
+    }
+
+    #[test]
+    fn test_real_input() {
+        let (ruleset, updates) = crate::parser::load_input();
+        let graph = Graph::new(&ruleset);
+
+        // All pages are part of one big cycle
+        let components = graph.strongly_connected_components();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), graph.nr_nodes());
+
+        // But the rules for every single update can be followed
+        for update in &updates {
+            assert!(graph.induced(&update.pages).is_acyclic());
+        }
+    }
+}
@@ -1,12 +1,21 @@
+mod export;
+mod graph;
+mod parser;
+mod precedence;
+mod report;
+
 use std::{
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
     fs::{self},
-    num::ParseIntError,
+    str::FromStr,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use parser::ParseError;
+use precedence::PrecedenceTable;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Rule {
     first: u32,
     second: u32,
@@ -16,13 +25,13 @@ impl Rule {
     fn new(first: u32, second: u32) -> Self {
         Self { first, second }
     }
+}
 
-    fn from_str(s: &str) -> Result<Self> {
-        let mut parts = s.split('|');
-        let first = parts.next().unwrap().parse()?;
-        let second = parts.next().unwrap().parse()?;
+impl FromStr for Rule {
+    type Err = ParseError;
 
-        Ok(Self::new(first, second))
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_rule(1, s)
     }
 }
 
@@ -36,11 +45,8 @@ impl RuleSet {
         Self { rules }
     }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let rules = s.lines().map(Rule::from_str).collect::<Result<Vec<_>>>()?;
-        Ok(Self::new(rules))
-    }
-
+    /// Checks every rule, see [`PrecedenceTable`] for the fast way.
+    #[cfg(test)]
     fn is_correctly_ordered(&self, update: &Update) -> bool {
         self.get_applicable_rules(update).iter().all(|rule| {
             let first = update.index_of(rule.first);
@@ -65,6 +71,15 @@ impl RuleSet {
     }
 }
 
+impl FromStr for RuleSet {
+    type Err = ParseError;
+
+    /// Parses one rule per line.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_rules(s.lines().enumerate().map(|(i, line)| (i + 1, line)))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Update {
     pages: Vec<u32>,
@@ -82,14 +97,6 @@ impl Update {
         }
     }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let pages = s
-            .split(',')
-            .map(str::parse)
-            .collect::<Result<Vec<_>, ParseIntError>>()?;
-        Ok(Self::new(pages))
-    }
-
     #[inline]
     fn middle_page(&self) -> u32 {
         let mid = self.pages.len() / 2;
@@ -106,56 +113,147 @@ impl Update {
         self.page_to_index.contains_key(&page)
     }
 
-    fn swap(&mut self, first_idx: usize, second_idx: usize) {
-        let first = self.pages[first_idx];
-        let second = self.pages[second_idx];
-
-        // Swap the pages
-        self.pages.swap(first_idx, second_idx);
-
-        // Update the page to index mapping
-        self.page_to_index.insert(first, second_idx);
-        self.page_to_index.insert(second, first_idx);
+    /// Orders the pages by comparing them with the precedence table.
+    ///
+    /// Returns `None` unless the rules order the pages of the update totally,
+    /// as the comparison isn't a valid order otherwise. Use
+    /// [`Update::sort_according_to`] for those updates.
+    fn sort_by_table(&self, table: &PrecedenceTable) -> Option<Self> {
+        if !table.is_total_order(&self.pages) {
+            return None;
+        }
+        let mut pages = self.pages.clone();
+        pages.sort_by(|a, b| table.compare(a, b));
+        Some(Self::new(pages))
     }
 
-    fn sort_according_to(&self, rules: &RuleSet) -> Self {
+    /// Orders the pages so that all applicable rules are followed.
+    ///
+    /// Uses Kahn's algorithm on the graph of applicable rules. Whenever
+    /// several pages could come next, the one appearing first in the update is
+    /// taken, so the result is deterministic and pages that aren't constrained
+    /// keep their relative order.
+    fn sort_according_to(&self, rules: &RuleSet) -> Result<Self> {
         let applicable_rules = rules.get_applicable_rules(self);
 
-        let mut prev = self.clone();
-        loop {
-            let mut cur = prev.clone();
-            for rule in applicable_rules.iter() {
-                let first = cur.index_of(rule.first).unwrap();
-                let second = cur.index_of(rule.second).unwrap();
-                if first > second {
-                    cur.swap(first, second);
+        let nr_pages = self.pages.len();
+        let mut successors = vec![Vec::new(); nr_pages];
+        let mut nr_predecessors = vec![0; nr_pages];
+        for rule in applicable_rules.iter() {
+            let first = self.index_of(rule.first).unwrap();
+            let second = self.index_of(rule.second).unwrap();
+            successors[first].push(second);
+            nr_predecessors[second] += 1;
+        }
+
+        let mut ready: BinaryHeap<_> = (0..nr_pages)
+            .filter(|&i| nr_predecessors[i] == 0)
+            .map(Reverse)
+            .collect();
+        let mut pages = Vec::with_capacity(nr_pages);
+        while let Some(Reverse(i)) = ready.pop() {
+            pages.push(self.pages[i]);
+            for &next in &successors[i] {
+                nr_predecessors[next] -= 1;
+                if nr_predecessors[next] == 0 {
+                    ready.push(Reverse(next));
                 }
             }
+        }
 
-            if cur.pages == prev.pages {
-                return cur;
-            }
-            prev = cur;
+        if pages.len() != nr_pages {
+            bail!(
+                "The rules for update {:?} contain a cycle, it can't be ordered",
+                self.pages
+            );
         }
+
+        Ok(Self::new(pages))
+    }
+}
+
+impl FromStr for Update {
+    type Err = ParseError;
+
+    /// Unlike in the puzzle input, updates of even length are accepted.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse_update(1, s, false)
     }
 }
 
 fn main() -> Result<()> {
     let input = fs::read_to_string("input.txt")?;
-    let (rules, updates) = input
-        .split_once("\n\n")
-        .ok_or(anyhow!("Couldn't find blank line!"))?;
+    let (ruleset, updates) = parser::parse(&input, true)?;
+    let table = PrecedenceTable::new(&ruleset);
+
+    let mut analyze = false;
+    let mut report = None;
+    let mut comparator = false;
+    let mut export = None;
+    let mut export_update = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--analyze" => analyze = true,
+            "--comparator" => comparator = true,
+            "--report" => {
+                let format = args
+                    .next()
+                    .ok_or(anyhow!("--report expects a format, text or json"))?;
+                report = Some(report::Format::from_str(&format)?);
+            }
+            "--export" => {
+                let format = args
+                    .next()
+                    .ok_or(anyhow!("--export expects a format, dot or mermaid"))?;
+                export = Some(export::ExportFormat::from_str(&format)?);
+            }
+            "--update" => {
+                let number: usize = args
+                    .next()
+                    .ok_or(anyhow!("--update expects the number of an update"))?
+                    .parse()?;
+                let update = number
+                    .checked_sub(1)
+                    .and_then(|i| updates.get(i))
+                    .ok_or(anyhow!("There is no update {}", number))?;
+                export_update = Some(update);
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
 
-    let ruleset = RuleSet::from_str(rules)?;
+    // Each of these picks what's printed, so only one of them can be given
+    let modes: Vec<_> = [
+        (analyze, "--analyze"),
+        (report.is_some(), "--report"),
+        (export.is_some(), "--export"),
+        (comparator, "--comparator"),
+    ]
+    .into_iter()
+    .filter_map(|(given, flag)| given.then_some(flag))
+    .collect();
+    if modes.len() > 1 {
+        bail!("{} can't be combined", modes.join(" and "));
+    }
+    if export_update.is_some() && export.is_none() {
+        bail!("--update only selects the update for --export");
+    }
 
-    let updates = updates
-        .lines()
-        .map(Update::from_str)
-        .collect::<Result<Vec<_>>>()?;
+    if analyze {
+        return graph::print_analysis(&ruleset, &updates);
+    }
+    if let Some(format) = report {
+        return report::print_reports(&ruleset, &updates, format);
+    }
+    if let Some(format) = export {
+        print!("{}", export::export(&ruleset, export_update, format));
+        return Ok(());
+    }
 
     let incorrectly_ordered_updates: Vec<_> = updates
         .iter()
-        .filter(|update| !ruleset.is_correctly_ordered(update))
+        .filter(|update| !table.is_correctly_ordered(&update.pages))
         .cloned()
         .collect();
 
@@ -165,10 +263,20 @@ fn main() -> Result<()> {
         updates.len()
     );
 
-    let sorted_updates: Vec<_> = incorrectly_ordered_updates
-        .iter()
-        .map(|u| u.sort_according_to(&ruleset))
-        .collect();
+    let sorted_updates: Vec<_> = if comparator {
+        incorrectly_ordered_updates
+            .iter()
+            .map(|u| match u.sort_by_table(&table) {
+                Some(sorted) => Ok(sorted),
+                None => u.sort_according_to(&ruleset),
+            })
+            .collect::<Result<_>>()?
+    } else {
+        incorrectly_ordered_updates
+            .iter()
+            .map(|u| u.sort_according_to(&ruleset))
+            .collect::<Result<_>>()?
+    };
 
     let res: u32 = sorted_updates.iter().map(|u| u.middle_page()).sum();
 
@@ -218,6 +326,90 @@ mod tests {
         assert!(!ruleset.is_correctly_ordered(&update));
     }
 
+    /// The previous implementation, which swaps pages breaking a rule until
+    /// nothing changes anymore.
+    fn sort_by_swapping(update: &Update, rules: &RuleSet) -> Update {
+        let applicable_rules = rules.get_applicable_rules(update);
+
+        let mut prev = update.clone();
+        loop {
+            let mut cur = prev.clone();
+            for rule in applicable_rules.iter() {
+                let first = cur.index_of(rule.first).unwrap();
+                let second = cur.index_of(rule.second).unwrap();
+                if first > second {
+                    cur.pages.swap(first, second);
+                    cur = Update::new(cur.pages);
+                }
+            }
+
+            if cur.pages == prev.pages {
+                return cur;
+            }
+            prev = cur;
+        }
+    }
+
+    #[test]
+    fn test_sort_according_to() {
+        let ruleset = RuleSet::from_str("1|2\n3|4\n3|5\n5|4").unwrap();
+        let update = Update::from_str("4,2,5,1,3").unwrap();
+        let sorted = update.sort_according_to(&ruleset).unwrap();
+        assert_eq!(sorted.pages, vec![1, 2, 3, 5, 4]);
+        assert!(ruleset.is_correctly_ordered(&sorted));
+        assert_eq!(sorted.index_of(5), Some(3));
+
+        // Pages without rules keep their order
+        let update = Update::from_str("9,8,7").unwrap();
+        assert_eq!(
+            update.sort_according_to(&ruleset).unwrap().pages,
+            update.pages
+        );
+    }
+
+    #[test]
+    fn test_sort_by_table_needs_total_order() {
+        let ruleset = RuleSet::from_str("1|3\n2|3").unwrap();
+        let table = PrecedenceTable::new(&ruleset);
+        assert!(Update::from_str("3,2,1")
+            .unwrap()
+            .sort_by_table(&table)
+            .is_none());
+
+        let ruleset = RuleSet::from_str("1|3\n2|3\n2|1").unwrap();
+        let table = PrecedenceTable::new(&ruleset);
+        let sorted = Update::from_str("3,1,2")
+            .unwrap()
+            .sort_by_table(&table)
+            .unwrap();
+        assert_eq!(sorted.pages, vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn test_sort_according_to_cycle() {
+        let ruleset = RuleSet::from_str("1|2\n2|3\n3|1").unwrap();
+        let update = Update::from_str("1,2,3").unwrap();
+        assert!(update.sort_according_to(&ruleset).is_err());
+
+        // The cycle only matters if all its pages are in the update
+        let update = Update::from_str("1,3").unwrap();
+        assert_eq!(
+            update.sort_according_to(&ruleset).unwrap().pages,
+            vec![3, 1]
+        );
+    }
+
+    #[test]
+    fn test_sort_agrees_with_swapping() {
+        let (ruleset, updates) = crate::parser::load_input();
+
+        for update in &updates {
+            let sorted = update.sort_according_to(&ruleset).unwrap();
+            assert!(ruleset.is_correctly_ordered(&sorted));
+            assert_eq!(sorted.pages, sort_by_swapping(update, &ruleset).pages);
+        }
+    }
+
     #[test]
     fn test_middle_page() {
         let update = Update::from_str("1,2,3,4,5").unwrap();
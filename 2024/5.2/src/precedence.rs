@@ -0,0 +1,139 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use crate::RuleSet;
+
+/// All the rules compiled into a set of ordered page pairs, to look up
+/// whether one page has to come before another in constant time.
+#[derive(Debug, Clone)]
+pub struct PrecedenceTable {
+    pairs: HashSet<(u32, u32)>,
+}
+
+impl PrecedenceTable {
+    pub fn new(rules: &RuleSet) -> Self {
+        Self {
+            pairs: rules
+                .rules
+                .iter()
+                .map(|rule| (rule.first, rule.second))
+                .collect(),
+        }
+    }
+
+    /// Whether a rule says that `first` has to come before `second`.
+    #[inline]
+    pub fn precedes(&self, first: u32, second: u32) -> bool {
+        self.pairs.contains(&(first, second))
+    }
+
+    /// Orders two pages by the rules, pages without a rule between them
+    /// compare as equal.
+    pub fn compare(&self, a: &u32, b: &u32) -> Ordering {
+        if self.precedes(*a, *b) {
+            Ordering::Less
+        } else if self.precedes(*b, *a) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    /// Checks that no page has to come before any page preceding it.
+    ///
+    /// Every pair of pages is looked up, as the rules don't have to relate
+    /// neighbouring pages: with only `1|3`, `3,2,1` is out of order.
+    pub fn is_correctly_ordered(&self, pages: &[u32]) -> bool {
+        pages.iter().enumerate().all(|(i, &first)| {
+            pages[i + 1..]
+                .iter()
+                .all(|&second| !self.precedes(second, first))
+        })
+    }
+
+    /// Checks that the rules order the pages completely and consistently, so
+    /// they can be sorted with [`PrecedenceTable::compare`].
+    ///
+    /// That's the case when there's a rule for every pair of pages and the
+    /// rules don't form a cycle, so that every page has to come before a
+    /// different number of the others.
+    pub fn is_total_order(&self, pages: &[u32]) -> bool {
+        let mut nr_successors = Vec::with_capacity(pages.len());
+        for (i, &a) in pages.iter().enumerate() {
+            let mut n = 0;
+            for (j, &b) in pages.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                match (self.precedes(a, b), self.precedes(b, a)) {
+                    (true, false) => n += 1,
+                    (false, true) => {}
+                    _ => return false,
+                }
+            }
+            nr_successors.push(n);
+        }
+
+        nr_successors.sort_unstable();
+        nr_successors.iter().enumerate().all(|(i, &n)| i == n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_precedes() {
+        let table = PrecedenceTable::new(&RuleSet::from_str("1|2\n3|4").unwrap());
+        assert!(table.precedes(1, 2));
+        assert!(!table.precedes(2, 1));
+        assert!(!table.precedes(1, 3));
+        assert_eq!(table.compare(&1, &2), Ordering::Less);
+        assert_eq!(table.compare(&4, &3), Ordering::Greater);
+        assert_eq!(table.compare(&1, &4), Ordering::Equal);
+        assert!(table.is_correctly_ordered(&[1, 2, 3, 4]));
+        assert!(!table.is_correctly_ordered(&[1, 4, 3, 2]));
+    }
+
+    #[test]
+    fn test_rule_between_distant_pages() {
+        let table = PrecedenceTable::new(&RuleSet::from_str("1|3").unwrap());
+        assert!(table.is_correctly_ordered(&[1, 2, 3]));
+        assert!(!table.is_correctly_ordered(&[3, 2, 1]));
+    }
+
+    #[test]
+    fn test_is_total_order() {
+        let table =
+            PrecedenceTable::new(&RuleSet::from_str("1|2\n2|3\n1|3\n4|5\n5|6\n6|4").unwrap());
+        assert!(table.is_total_order(&[3, 1, 2]));
+        assert!(table.is_total_order(&[2, 1]));
+        assert!(table.is_total_order(&[]));
+        // 1 and 4 aren't related
+        assert!(!table.is_total_order(&[1, 2, 4]));
+        // 4, 5 and 6 form a cycle
+        assert!(!table.is_total_order(&[4, 5, 6]));
+    }
+
+    #[test]
+    fn test_agrees_with_rules() {
+        let (ruleset, updates) = crate::parser::load_input();
+        let table = PrecedenceTable::new(&ruleset);
+
+        for update in &updates {
+            assert_eq!(
+                table.is_correctly_ordered(&update.pages),
+                ruleset.is_correctly_ordered(update)
+            );
+
+            let sorted = update.sort_by_table(&table).unwrap();
+            assert!(table.is_correctly_ordered(&sorted.pages));
+            assert_eq!(
+                sorted.pages,
+                update.sort_according_to(&ruleset).unwrap().pages
+            );
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt::{self, Display};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::{RuleSet, Update};
+
+/// How the report is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => bail!("Unknown report format '{}', expected text or json", s),
+        }
+    }
+}
+
+/// A rule which isn't followed, with the positions of its pages.
+///
+/// Positions are indices into the update, starting at 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub first: u32,
+    pub second: u32,
+    pub first_position: usize,
+    pub second_position: usize,
+}
+
+/// Takes `page` out at position `from` and puts it back in at position `to`,
+/// both relative to the pages as they are right before the move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Move {
+    pub page: u32,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Everything wrong with one incorrectly ordered update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpdateReport {
+    /// The number of the update in the input, starting at 1.
+    pub update: usize,
+    pub pages: Vec<u32>,
+    pub violations: Vec<Violation>,
+    pub corrected: Vec<u32>,
+    pub moves: Vec<Move>,
+}
+
+fn join(pages: &[u32]) -> String {
+    pages
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Display for UpdateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Update {}: {}", self.update, join(&self.pages))?;
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  violates {}|{} (positions {} and {})",
+                violation.first,
+                violation.second,
+                violation.first_position,
+                violation.second_position
+            )?;
+        }
+        writeln!(f, "  corrected: {}", join(&self.corrected))?;
+        write!(f, "  {} move(s):", self.moves.len())?;
+        for m in &self.moves {
+            write!(
+                f,
+                "\n    move {} from position {} to {}",
+                m.page, m.from, m.to
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the rules the update doesn't follow.
+pub fn violations(ruleset: &RuleSet, update: &Update) -> Vec<Violation> {
+    ruleset
+        .get_applicable_rules(update)
+        .iter()
+        .filter_map(|rule| {
+            let first_position = update.index_of(rule.first).unwrap();
+            let second_position = update.index_of(rule.second).unwrap();
+            (first_position > second_position).then_some(Violation {
+                first: rule.first,
+                second: rule.second,
+                first_position,
+                second_position,
+            })
+        })
+        .collect()
+}
+
+/// Finds the fewest moves turning `pages` into `target`, which has to be a
+/// permutation of it.
+///
+/// The pages forming the longest subsequence which is already in the right
+/// order stay where they are, all others are moved once, right behind the
+/// page preceding them in the target.
+pub fn minimal_moves(pages: &[u32], target: &[u32]) -> Vec<Move> {
+    let rank = |page: u32| target.iter().position(|&p| p == page).unwrap();
+    let ranks: Vec<_> = pages.iter().map(|&page| rank(page)).collect();
+
+    // Longest increasing subsequence of the ranks, by patience sorting
+    let mut tails: Vec<usize> = Vec::new();
+    let mut predecessors = vec![None; ranks.len()];
+    for (i, &r) in ranks.iter().enumerate() {
+        let pile = tails.partition_point(|&j| ranks[j] < r);
+        predecessors[i] = pile.checked_sub(1).map(|p| tails[p]);
+        if pile == tails.len() {
+            tails.push(i);
+        } else {
+            tails[pile] = i;
+        }
+    }
+    let mut keep = vec![false; target.len()];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        keep[ranks[i]] = true;
+        next = predecessors[i];
+    }
+
+    let mut current = pages.to_vec();
+    let mut moves = Vec::new();
+    for (r, &page) in target.iter().enumerate() {
+        if keep[r] {
+            continue;
+        }
+        let from = current.iter().position(|&p| p == page).unwrap();
+        current.remove(from);
+        let to = match r {
+            0 => 0,
+            _ => current.iter().position(|&p| p == target[r - 1]).unwrap() + 1,
+        };
+        current.insert(to, page);
+        moves.push(Move { page, from, to });
+    }
+    moves
+}
+
+/// Reports the violated rules and the moves fixing the update, if it isn't
+/// correctly ordered.
+pub fn report(ruleset: &RuleSet, number: usize, update: &Update) -> Result<Option<UpdateReport>> {
+    let violations = violations(ruleset, update);
+    if violations.is_empty() {
+        return Ok(None);
+    }
+
+    let corrected = update.sort_according_to(ruleset)?.pages;
+    let moves = minimal_moves(&update.pages, &corrected);
+    Ok(Some(UpdateReport {
+        update: number,
+        pages: update.pages.clone(),
+        violations,
+        corrected,
+        moves,
+    }))
+}
+
+/// Prints the report of every incorrectly ordered update.
+pub fn print_reports(ruleset: &RuleSet, updates: &[Update], format: Format) -> Result<()> {
+    let mut reports = Vec::new();
+    for (i, update) in updates.iter().enumerate() {
+        reports.extend(report(ruleset, i + 1, update)?);
+    }
+
+    match format {
+        Format::Text => {
+            for report in &reports {
+                println!("{}", report);
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn apply(pages: &[u32], moves: &[Move]) -> Vec<u32> {
+        let mut pages = pages.to_vec();
+        for m in moves {
+            assert_eq!(pages.remove(m.from), m.page);
+            pages.insert(m.to, m.page);
+        }
+        pages
+    }
+
+    #[test]
+    fn test_minimal_moves() {
+        assert_eq!(minimal_moves(&[1, 2, 3], &[1, 2, 3]), vec![]);
+        assert_eq!(
+            minimal_moves(&[2, 3, 4, 1], &[1, 2, 3, 4]),
+            vec![Move {
+                page: 1,
+                from: 3,
+                to: 0
+            }]
+        );
+
+        let moves = minimal_moves(&[5, 4, 3, 2, 1], &[1, 2, 3, 4, 5]);
+        assert_eq!(moves.len(), 4);
+        assert_eq!(apply(&[5, 4, 3, 2, 1], &moves), vec![1, 2, 3, 4, 5]);
+
+        let moves = minimal_moves(&[3, 1, 5, 2, 6, 4], &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(moves.len(), 3);
+        assert_eq!(apply(&[3, 1, 5, 2, 6, 4], &moves), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_report() {
+        let ruleset = RuleSet::from_str(
+            "47|53\n97|61\n97|47\n75|53\n61|53\n97|53\n75|47\n97|75\n47|61\n75|61",
+        )
+        .unwrap();
+        let update = Update::from_str("75,97,47,61,53").unwrap();
+        let report = report(&ruleset, 3, &update).unwrap().unwrap();
+        assert_eq!(
+            report.violations,
+            vec![Violation {
+                first: 97,
+                second: 75,
+                first_position: 1,
+                second_position: 0
+            }]
+        );
+        assert_eq!(report.corrected, vec![97, 75, 47, 61, 53]);
+        assert_eq!(report.moves.len(), 1);
+        assert_eq!(
+            report.to_string(),
+            "Update 3: 75,97,47,61,53\n  violates 97|75 (positions 1 and 0)\n  \
+             corrected: 97,75,47,61,53\n  1 move(s):\n    move 75 from position 0 to 1"
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["violations"][0]["first_position"], 1);
+        assert_eq!(json["moves"][0]["page"], 75);
+
+        let update = Update::from_str("75,47,61,53").unwrap();
+        assert_eq!(super::report(&ruleset, 1, &update).unwrap(), None);
+    }
+}
@@ -1,14 +1,16 @@
+mod simulation;
+
 use anyhow::Result;
-use std::fs;
+use simulation::{Simulation, Step};
+use std::{collections::HashSet, fs};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum State {
     Empty,
     Wall,
-    Visited,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Direction {
     Up,
     Down,
@@ -16,14 +18,25 @@ enum Direction {
     Right,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl Direction {
+    fn next(&self) -> Self {
+        match self {
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Coordinate {
-    row: usize,
-    col: usize,
+    row: isize,
+    col: isize,
 }
 
 impl Coordinate {
-    fn new(row: usize, col: usize) -> Self {
+    fn new(row: isize, col: isize) -> Self {
         Self { row, col }
     }
 }
@@ -35,105 +48,95 @@ struct Board {
 }
 
 impl Board {
-    fn from_str(input: &str) -> Self {
+    fn from_str(input: &str) -> (Self, Guard) {
+        let width = input.find('\n').unwrap();
+
+        let mut guard_loc = None;
         let data = input
             .lines()
             .flat_map(|line| line.bytes())
-            .map(|byte| match byte {
+            .enumerate()
+            .map(|(i, byte)| match byte {
                 b'.' => State::Empty,
                 b'#' => State::Wall,
-                b'^' => State::Visited,
+                b'^' => {
+                    guard_loc = Some(Coordinate::new((i / width) as isize, (i % width) as isize));
+                    State::Empty
+                }
                 _ => panic!("Invalid byte {}", byte),
             })
             .collect::<Vec<_>>();
 
-        let width = input.find('\n').unwrap();
         let height = data.len() / width;
 
-        Self {
-            width,
-            height,
-            data,
-        }
+        (
+            Self {
+                width,
+                height,
+                data,
+            },
+            Guard::new(guard_loc.unwrap(), Direction::Up),
+        )
     }
 
     fn get(&self, loc: &Coordinate) -> Option<State> {
-        if loc.row >= self.height || loc.col >= self.width {
+        if !self.contains(loc) {
             return None;
         }
-        self.data.get(loc.row * self.width + loc.col).copied()
+        Some(self.data[loc.row as usize * self.width + loc.col as usize])
     }
 
-    fn set(&mut self, loc: &Coordinate, state: State) {
-        self.data[loc.row * self.width + loc.col] = state;
+    fn contains(&self, loc: &Coordinate) -> bool {
+        (0..self.height as isize).contains(&loc.row) && (0..self.width as isize).contains(&loc.col)
     }
+}
 
-    fn find_start(&self) -> Coordinate {
-        self.data
-            .iter()
-            .enumerate()
-            .find_map(|(i, &state)| {
-                (state == State::Visited).then(|| Coordinate::new(i / self.width, i % self.width))
-            })
-            .unwrap()
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Guard {
+    loc: Coordinate,
+    direction: Direction,
+}
 
-    fn walk_to_wall(&mut self, guard: &Guard) -> Option<Coordinate> {
-        let mut loc = guard.loc;
+impl Guard {
+    fn new(loc: Coordinate, direction: Direction) -> Self {
+        Self { loc, direction }
+    }
 
-        let mut prev = loc;
-        loop {
-            match self.get(&loc) {
-                Some(State::Wall) => return Some(prev),
-                Some(State::Empty) => {
-                    self.set(&loc, State::Visited);
-                }
-                Some(State::Visited) => {}
-                None => return None,
-            }
-
-            prev = loc;
-            match guard.direction {
-                Direction::Up => loc.row = loc.row.checked_sub(1)?,
-                Direction::Down => loc.row += 1,
-                Direction::Left => loc.col = loc.col.checked_sub(1)?,
-                Direction::Right => loc.col += 1,
-            }
+    fn next_loc(&self) -> Coordinate {
+        let mut loc = self.loc;
+        match self.direction {
+            Direction::Up => loc.row -= 1,
+            Direction::Down => loc.row += 1,
+            Direction::Left => loc.col -= 1,
+            Direction::Right => loc.col += 1,
         }
+        loc
+    }
+
+    fn turn(&mut self) {
+        self.direction = self.direction.next();
+    }
+
+    fn move_forward(&mut self) {
+        self.loc = self.next_loc();
     }
 }
 
-struct Guard {
-    loc: Coordinate,
-    direction: Direction,
+fn count_visited(board: &Board, guard: Guard) -> usize {
+    let mut visited = HashSet::from([guard.loc]);
+    for step in Simulation::new(board, guard) {
+        if let Step::Moved(guard) = step {
+            visited.insert(guard.loc);
+        }
+    }
+    visited.len()
 }
 
 fn main() -> Result<()> {
     let input = fs::read_to_string("input.txt")?;
-    let mut board = Board::from_str(&input);
-
-    let mut guard = Guard {
-        loc: board.find_start(),
-        direction: Direction::Up,
-    };
-
-    while let Some(new_loc) = board.walk_to_wall(&guard) {
-        let new_direction = match guard.direction {
-            Direction::Up => Direction::Right,
-            Direction::Right => Direction::Down,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-        };
-
-        guard.loc = new_loc;
-        guard.direction = new_direction;
-    }
+    let (board, guard) = Board::from_str(&input);
 
-    let result = board
-        .data
-        .iter()
-        .filter(|&&state| state == State::Visited)
-        .count();
+    let result = count_visited(&board, guard);
 
     println!("{}", result);
 
@@ -141,4 +144,24 @@ fn main() -> Result<()> {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    pub const INPUT1: &str = r#"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."#;
+
+    #[test]
+    fn test_count_visited() {
+        let (board, guard) = Board::from_str(INPUT1);
+
+        assert_eq!(count_visited(&board, guard), 41);
+    }
+}
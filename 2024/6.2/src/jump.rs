@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use crate::{
+    simulation::{Simulation, Step},
+    Board, Coordinate, Direction, Guard, State,
+};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+impl Direction {
+    /// Position in [`DIRECTIONS`], turning right goes to the next one.
+    fn index(self) -> usize {
+        match self {
+            Self::Up => 0,
+            Self::Right => 1,
+            Self::Down => 2,
+            Self::Left => 3,
+        }
+    }
+}
+
+/// For every cell and direction, the cell the guard stops at in front of the
+/// next wall, so a simulation can jump from wall to wall.
+pub struct JumpTable {
+    width: usize,
+    height: usize,
+    /// Indexed by direction and then cell, `None` if the guard walks off the
+    /// board instead.
+    stops: [Vec<Option<usize>>; 4],
+    /// The turns taken by the current loop check, stamped with its number so
+    /// they don't have to be cleared in between.
+    seen: Vec<u32>,
+    stamp: u32,
+}
+
+impl JumpTable {
+    pub fn new(board: &Board) -> Self {
+        let (width, height) = (board.width, board.height);
+        let is_wall = |row: usize, col: usize| {
+            board.get(&Coordinate::new(row as isize, col as isize)) == Some(State::Wall)
+        };
+
+        let mut stops: [Vec<Option<usize>>; 4] = Default::default();
+        for stops in &mut stops {
+            *stops = vec![None; width * height];
+        }
+
+        // Sweep every line against the walking direction, remembering the
+        // cell in front of the last wall passed
+        for col in 0..width {
+            let mut stop = None;
+            for row in 0..height {
+                if is_wall(row, col) {
+                    stop = Some((row + 1) * width + col);
+                } else {
+                    stops[Direction::Up.index()][row * width + col] = stop;
+                }
+            }
+            let mut stop = None;
+            for row in (0..height).rev() {
+                if is_wall(row, col) {
+                    stop = row.checked_sub(1).map(|row| row * width + col);
+                } else {
+                    stops[Direction::Down.index()][row * width + col] = stop;
+                }
+            }
+        }
+        for row in 0..height {
+            let mut stop = None;
+            for col in 0..width {
+                if is_wall(row, col) {
+                    stop = Some(row * width + col + 1);
+                } else {
+                    stops[Direction::Left.index()][row * width + col] = stop;
+                }
+            }
+            let mut stop = None;
+            for col in (0..width).rev() {
+                if is_wall(row, col) {
+                    stop = col.checked_sub(1).map(|col| row * width + col);
+                } else {
+                    stops[Direction::Right.index()][row * width + col] = stop;
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            stops,
+            seen: vec![0; width * height * 4],
+            stamp: 0,
+        }
+    }
+
+    /// How many steps `to` is ahead of `from` when walking in `direction`, if
+    /// it's on the way at all.
+    fn steps_ahead(&self, from: usize, to: usize, direction: Direction) -> Option<usize> {
+        let (from_row, from_col) = (from / self.width, from % self.width);
+        let (to_row, to_col) = (to / self.width, to % self.width);
+        match direction {
+            Direction::Up if to_col == from_col && to_row < from_row => Some(from_row - to_row),
+            Direction::Down if to_col == from_col && to_row > from_row => Some(to_row - from_row),
+            Direction::Left if to_row == from_row && to_col < from_col => Some(from_col - to_col),
+            Direction::Right if to_row == from_row && to_col > from_col => Some(to_col - from_col),
+            _ => None,
+        }
+    }
+
+    /// The cell the guard stops at, with an additional obstacle on the board.
+    ///
+    /// This is the same as patching the obstacle into the stops of its row and
+    /// column: the stop only changes if the obstacle comes before it.
+    fn stop(&self, cell: usize, direction: Direction, obstacle: usize) -> Option<usize> {
+        let stop = self.stops[direction.index()][cell];
+        let Some(obstacle_steps) = self.steps_ahead(cell, obstacle, direction) else {
+            return stop;
+        };
+        let stop_steps = match stop {
+            Some(stop) => self.steps_ahead(cell, stop, direction).unwrap_or(0),
+            None => usize::MAX,
+        };
+        if obstacle_steps > stop_steps {
+            return stop;
+        }
+
+        // The cell right in front of the obstacle
+        Some(match direction {
+            Direction::Up => obstacle + self.width,
+            Direction::Down => obstacle - self.width,
+            Direction::Left => obstacle + 1,
+            Direction::Right => obstacle - 1,
+        })
+    }
+
+    fn cell(&self, loc: &Coordinate) -> usize {
+        loc.row as usize * self.width + loc.col as usize
+    }
+
+    /// Checks whether the guard ends up in a loop with an additional obstacle.
+    pub fn has_loop(&mut self, guard: Guard, obstacle: &Coordinate) -> bool {
+        self.stamp += 1;
+        let obstacle = self.cell(obstacle);
+        let mut cell = self.cell(&guard.loc);
+        let mut direction = guard.direction;
+        loop {
+            let Some(stop) = self.stop(cell, direction, obstacle) else {
+                return false;
+            };
+
+            // Turning at the same spot in the same direction twice is a loop
+            let turn = stop * DIRECTIONS.len() + direction.index();
+            if self.seen[turn] == self.stamp {
+                return true;
+            }
+            self.seen[turn] = self.stamp;
+
+            cell = stop;
+            direction = direction.next();
+        }
+    }
+
+    /// Finds all the cells an obstacle can be put on to make the guard loop.
+    ///
+    /// Follows the guard's path once, and checks every cell the first time the
+    /// guard walks into it. As the obstacle isn't on the path before that, the
+    /// check can start from the state the guard was in right before.
+    pub fn count_loops(&mut self, board: &Board, guard: Guard) -> HashSet<Coordinate> {
+        let mut tried = vec![false; self.width * self.height];
+        tried[self.cell(&guard.loc)] = true;
+
+        let mut new_obstacles = HashSet::new();
+        let mut prev = guard;
+        for step in Simulation::new(board, guard) {
+            match step {
+                Step::Moved(next) => {
+                    let cell = self.cell(&next.loc);
+                    if !tried[cell] {
+                        tried[cell] = true;
+                        if self.has_loop(prev, &next.loc) {
+                            new_obstacles.insert(next.loc);
+                        }
+                    }
+                    prev = next;
+                }
+                Step::Turned(next) => prev = next,
+                Step::Exited(_) | Step::LoopDetected(_) => {}
+            }
+        }
+        new_obstacles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        tests::{INPUT1, INPUT2},
+        Game,
+    };
+
+    fn count_loops(input: &str) -> HashSet<Coordinate> {
+        let (board, guard) = Board::from_str(input);
+        JumpTable::new(&board).count_loops(&board, guard)
+    }
+
+    #[test]
+    fn test_stops() {
+        let (board, _) = Board::from_str(INPUT1);
+        let table = JumpTable::new(&board);
+        // The guard starts at row 6, col 4, and stops below the wall at row 0
+        let start = 6 * 10 + 4;
+        assert_eq!(table.stops[Direction::Up.index()][start], Some(10 + 4));
+        assert_eq!(table.stops[Direction::Right.index()][10 + 4], Some(10 + 8));
+        assert_eq!(
+            table.stops[Direction::Left.index()][start],
+            Some(6 * 10 + 2)
+        );
+        assert_eq!(table.stops[Direction::Down.index()][start], None);
+    }
+
+    #[test]
+    fn test_count_loops() {
+        assert_eq!(count_loops(INPUT1).len(), 6);
+        assert_eq!(count_loops(INPUT2).len(), 1);
+    }
+
+    #[test]
+    fn test_agrees_with_simulation() {
+        for input in [INPUT1.to_string(), fs::read_to_string("input.txt").unwrap()] {
+            let expected = Game::from_str(&input).count_loops();
+            assert_eq!(count_loops(&input), expected);
+        }
+    }
+}
@@ -1,5 +1,13 @@
-use anyhow::Result;
+mod jump;
+mod simulation;
+mod trajectory;
+
+use anyhow::{anyhow, bail, Result};
+use jump::JumpTable;
+use serde::Serialize;
+use simulation::{Simulation, Step};
 use std::{collections::HashSet, fs};
+use trajectory::Trajectory;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum State {
@@ -7,7 +15,7 @@ enum State {
     Wall,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 enum Direction {
     Up,
     Down,
@@ -26,7 +34,7 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 struct Coordinate {
     row: isize,
     col: isize,
@@ -103,7 +111,7 @@ impl Board {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 struct Guard {
     loc: Coordinate,
     direction: Direction,
@@ -129,10 +137,6 @@ impl Guard {
         self.direction.next()
     }
 
-    fn next_state(&self) -> Self {
-        Guard::new(self.next_loc(), self.direction)
-    }
-
     fn turn(&mut self) {
         self.direction = self.next_direction();
     }
@@ -158,54 +162,84 @@ impl Game {
         Self::new(board, guard)
     }
 
-    fn count_loops(&mut self) -> HashSet<Coordinate> {
-        let orig_guard = self.guard;
+    fn count_loops(&self) -> HashSet<Coordinate> {
         let mut new_obstacles = HashSet::new();
-        loop {
-            // Inspect the next location
-            let next_loc = self.guard.next_loc();
-            match self.board.get(&next_loc) {
-                Some(State::Wall) => self.guard.turn(),
-                Some(State::Empty) => {
-                    let tmp_board = self.board.with_additional_wall(&next_loc);
-                    let mut tmp_game = Game::new(tmp_board, orig_guard);
-                    if tmp_game.has_loop() {
-                        new_obstacles.insert(next_loc);
-                    }
-
-                    self.guard.move_forward();
+        for step in Simulation::new(&self.board, self.guard) {
+            // Try an obstacle on every cell the guard walks into
+            if let Step::Moved(guard) = step {
+                let tmp_board = self.board.with_additional_wall(&guard.loc);
+                let tmp_game = Game::new(tmp_board, self.guard);
+                if tmp_game.has_loop() {
+                    new_obstacles.insert(guard.loc);
                 }
-                None => return new_obstacles,
             }
         }
+        new_obstacles
     }
 
-    fn has_loop(&mut self) -> bool {
-        let mut visited_turns = HashSet::new();
-        loop {
-            // Inspect the next location
-            let next = self.guard.next_state();
-            match self.board.get(&next.loc) {
-                Some(State::Wall) => {
-                    // If we turn twice in the same location with the same direction,
-                    // we have a loop
-                    if !visited_turns.insert(next) {
-                        return true;
-                    }
-                    self.guard.turn();
-                }
-                Some(State::Empty) => self.guard.move_forward(),
-                None => return false,
-            }
-        }
+    fn has_loop(&self) -> bool {
+        matches!(
+            Simulation::new(&self.board, self.guard).last(),
+            Some(Step::LoopDetected(_))
+        )
     }
 }
 
 fn main() -> Result<()> {
+    let mut naive = false;
+    let mut trajectory = None;
+    let mut obstacle = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--naive" => naive = true,
+            "--trajectory" => {
+                trajectory = Some(
+                    args.next()
+                        .ok_or(anyhow!("--trajectory expects a format, csv or json"))?,
+                );
+            }
+            "--obstacle" => {
+                let loc = args
+                    .next()
+                    .ok_or(anyhow!("--obstacle expects a location like 6,3"))?;
+                let (row, col) = loc
+                    .split_once(',')
+                    .ok_or(anyhow!("Invalid location '{}'", loc))?;
+                obstacle = Some(Coordinate::new(row.parse()?, col.parse()?));
+            }
+            _ => bail!("Unknown argument '{}'", arg),
+        }
+    }
+
     let input = fs::read_to_string("input.txt")?;
-    let mut game = Game::from_str(&input);
+    let game = Game::from_str(&input);
+
+    if let Some(format) = trajectory {
+        let mut board = game.board.clone();
+        if let Some(obstacle) = obstacle {
+            if !board.contains(&obstacle) {
+                bail!("The obstacle {:?} isn't on the board", obstacle);
+            }
+            board.set(&obstacle, State::Wall);
+        }
+        let trajectory = Trajectory::record(&board, game.guard);
+        print!("{}", trajectory.export(&format)?);
+        // Keep stdout to the export, so it can be piped into a file
+        if let Some(cycle) = trajectory.cycle() {
+            eprintln!("The guard is stuck in a loop of {} states", cycle.len());
+        }
+        return Ok(());
+    }
+    if obstacle.is_some() {
+        bail!("--obstacle only changes the board for --trajectory");
+    }
 
-    let result = game.count_loops();
+    let result = if naive {
+        game.count_loops()
+    } else {
+        JumpTable::new(&game.board).count_loops(&game.board, game.guard)
+    };
 
     println!("{}", result.len());
 
@@ -216,7 +250,7 @@ fn main() -> Result<()> {
 mod tests {
     use super::*;
 
-    const INPUT1: &str = r#"....#.....
+    pub const INPUT1: &str = r#"....#.....
 .........#
 ..........
 ..#.......
@@ -229,14 +263,14 @@ mod tests {
 
     #[test]
     fn test_count_loops() {
-        let mut game = Game::from_str(INPUT1);
+        let game = Game::from_str(INPUT1);
 
         let result = game.count_loops();
 
         assert_eq!(result.len(), 6);
     }
 
-    const INPUT2: &str = r#"............#................##.
+    pub const INPUT2: &str = r#"............#................##.
 ...............................#
 ................................
 .^..............................
@@ -259,7 +293,7 @@ mod tests {
 
     #[test]
     fn test_count_special_loops() {
-        let mut game = Game::from_str(INPUT2);
+        let game = Game::from_str(INPUT2);
 
         let result = game.count_loops();
 
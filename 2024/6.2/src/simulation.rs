@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::{Board, Guard, State};
+
+/// What happens in a single step of the guard's walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The guard moved forward, and is now in this state.
+    Moved(Guard),
+    /// The guard faced a wall and turned right in place, and is now in this
+    /// state.
+    Turned(Guard),
+    /// The guard walked off the board from this state.
+    Exited(Guard),
+    /// The guard is about to take a turn it already took from this state, so
+    /// it keeps walking in circles from here on.
+    LoopDetected(Guard),
+}
+
+/// Walks the guard over the board one step at a time, ending with either
+/// [`Step::Exited`] or [`Step::LoopDetected`].
+///
+/// Every part is its own crate, so this module is a copy of the one in 6.1.
+/// Changes have to be made to both.
+pub struct Simulation<'a> {
+    board: &'a Board,
+    guard: Guard,
+    /// The states the guard turned from. Every loop has at least one turn, so
+    /// taking the same turn twice means the guard is stuck.
+    turns: HashSet<Guard>,
+    done: bool,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(board: &'a Board, guard: Guard) -> Self {
+        Self {
+            board,
+            guard,
+            turns: HashSet::new(),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Simulation<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        if self.done {
+            return None;
+        }
+
+        let step = match self.board.get(&self.guard.next_loc()) {
+            Some(State::Wall) => {
+                if !self.turns.insert(self.guard) {
+                    Step::LoopDetected(self.guard)
+                } else {
+                    self.guard.turn();
+                    Step::Turned(self.guard)
+                }
+            }
+            Some(State::Empty) => {
+                self.guard.move_forward();
+                Step::Moved(self.guard)
+            }
+            None => Step::Exited(self.guard),
+        };
+        self.done = matches!(step, Step::Exited(_) | Step::LoopDetected(_));
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::INPUT1, Coordinate, Direction};
+
+    #[test]
+    fn test_exit() {
+        let (board, guard) = Board::from_str(INPUT1);
+        let steps: Vec<_> = Simulation::new(&board, guard).collect();
+
+        assert_eq!(
+            steps[..6],
+            [
+                Step::Moved(Guard::new(Coordinate::new(5, 4), Direction::Up)),
+                Step::Moved(Guard::new(Coordinate::new(4, 4), Direction::Up)),
+                Step::Moved(Guard::new(Coordinate::new(3, 4), Direction::Up)),
+                Step::Moved(Guard::new(Coordinate::new(2, 4), Direction::Up)),
+                Step::Moved(Guard::new(Coordinate::new(1, 4), Direction::Up)),
+                Step::Turned(Guard::new(Coordinate::new(1, 4), Direction::Right)),
+            ]
+        );
+        assert_eq!(
+            steps.last(),
+            Some(&Step::Exited(Guard::new(
+                Coordinate::new(9, 7),
+                Direction::Down
+            )))
+        );
+    }
+
+    #[test]
+    fn test_loop() {
+        // An obstacle next to the guard's starting position
+        let (board, guard) = Board::from_str(&INPUT1.replace(".#..^", ".#.#^"));
+        let mut simulation = Simulation::new(&board, guard);
+
+        assert!(matches!(
+            simulation.by_ref().last(),
+            Some(Step::LoopDetected(_))
+        ));
+        assert_eq!(simulation.next(), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+use crate::{
+    simulation::{Simulation, Step},
+    Board, Guard,
+};
+
+/// How the guard's walk ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum End {
+    /// The guard walked off the board from the last recorded state.
+    Exited,
+    /// The guard came back to the state at `cycle_start` after the last
+    /// recorded state, and keeps walking in circles from there.
+    Loop { cycle_start: usize },
+}
+
+/// Every state the guard is in on its walk, in order.
+///
+/// Turning in place is a state of its own, with the same location and the new
+/// direction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Trajectory {
+    pub states: Vec<Guard>,
+    /// Indices of the states the guard turned into.
+    pub turns: Vec<usize>,
+    pub end: End,
+}
+
+impl Trajectory {
+    pub fn record(board: &Board, guard: Guard) -> Self {
+        let mut seen = HashMap::from([(guard, 0)]);
+        let mut states = vec![guard];
+        let mut turns = Vec::new();
+        for step in Simulation::new(board, guard) {
+            let guard = match step {
+                Step::Moved(guard) => guard,
+                Step::Turned(guard) => {
+                    turns.push(states.len());
+                    guard
+                }
+                Step::Exited(_) => break,
+                // Every state is recorded, so a state repeats before a turn does
+                Step::LoopDetected(_) => unreachable!("The loop wasn't found in the states"),
+            };
+
+            if let Some(&cycle_start) = seen.get(&guard) {
+                // The repeated state isn't a turn of its own
+                if turns.last() == Some(&states.len()) {
+                    turns.pop();
+                }
+                return Self {
+                    states,
+                    turns,
+                    end: End::Loop { cycle_start },
+                };
+            }
+            seen.insert(guard, states.len());
+            states.push(guard);
+        }
+
+        Self {
+            states,
+            turns,
+            end: End::Exited,
+        }
+    }
+
+    /// The states the guard keeps repeating, if it's stuck in a loop.
+    pub fn cycle(&self) -> Option<&[Guard]> {
+        match self.end {
+            End::Loop { cycle_start } => Some(&self.states[cycle_start..]),
+            End::Exited => None,
+        }
+    }
+
+    /// Writes one line per state with what happened to get there, `start`,
+    /// `move` or `turn`, and whether it's part of the loop.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,row,col,direction,event,in_cycle\n");
+        let cycle_start = match self.end {
+            End::Loop { cycle_start } => cycle_start,
+            End::Exited => self.states.len(),
+        };
+        let mut turns = self.turns.iter().peekable();
+        for (step, guard) in self.states.iter().enumerate() {
+            let event = if step == 0 {
+                "start"
+            } else if turns.next_if_eq(&&step).is_some() {
+                "turn"
+            } else {
+                "move"
+            };
+            csv.push_str(&format!(
+                "{},{},{},{:?},{},{}\n",
+                step,
+                guard.loc.row,
+                guard.loc.col,
+                guard.direction,
+                event,
+                step >= cycle_start
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn export(&self, format: &str) -> Result<String> {
+        match format {
+            "csv" => Ok(self.to_csv()),
+            "json" => self.to_json(),
+            _ => bail!(
+                "Unknown trajectory format '{}', expected csv or json",
+                format
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::INPUT1, Coordinate, Direction, State};
+
+    #[test]
+    fn test_exit() {
+        let (board, guard) = Board::from_str(INPUT1);
+        let trajectory = Trajectory::record(&board, guard);
+        assert_eq!(trajectory.end, End::Exited);
+        assert_eq!(trajectory.cycle(), None);
+        assert_eq!(trajectory.states[0], guard);
+        assert_eq!(trajectory.turns.len(), 10);
+
+        // The guard visits 41 distinct cells and leaves at the bottom
+        let mut cells: Vec<_> = trajectory.states.iter().map(|g| g.loc).collect();
+        cells.sort_by_key(|loc| (loc.row, loc.col));
+        cells.dedup();
+        assert_eq!(cells.len(), 41);
+        let last = trajectory.states.last().unwrap();
+        assert_eq!(last.loc, Coordinate::new(9, 7));
+        assert_eq!(last.direction, Direction::Down);
+
+        // Turns are in place
+        let first_turn = trajectory.turns[0];
+        assert_eq!(
+            trajectory.states[first_turn].loc,
+            trajectory.states[first_turn - 1].loc
+        );
+        assert_eq!(trajectory.states[first_turn].direction, Direction::Right);
+    }
+
+    #[test]
+    fn test_loop() {
+        let (board, guard) = Board::from_str(INPUT1);
+        let board = board.with_additional_wall(&Coordinate::new(6, 3));
+        let trajectory = Trajectory::record(&board, guard);
+
+        let cycle = trajectory.cycle().unwrap();
+        assert!(!cycle.is_empty());
+        // Going on from the last state leads back to the start of the cycle
+        let End::Loop { cycle_start } = trajectory.end else {
+            unreachable!()
+        };
+        let mut next = *trajectory.states.last().unwrap();
+        match board.get(&next.next_loc()) {
+            Some(State::Wall) => next.turn(),
+            _ => next.move_forward(),
+        }
+        assert_eq!(next, trajectory.states[cycle_start]);
+    }
+
+    #[test]
+    fn test_export() {
+        let (board, guard) = Board::from_str("#..\n...\n.^.\n");
+        let board = board.with_additional_wall(&Coordinate::new(0, 1));
+        let trajectory = Trajectory::record(&board, guard);
+        assert_eq!(
+            trajectory.to_csv(),
+            "step,row,col,direction,event,in_cycle\n\
+             0,2,1,Up,start,false\n\
+             1,1,1,Up,move,false\n\
+             2,1,1,Right,turn,false\n\
+             3,1,2,Right,move,false\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&trajectory.to_json().unwrap()).unwrap();
+        assert_eq!(json["end"]["type"], "exited");
+        assert_eq!(json["turns"][0], 2);
+        assert_eq!(json["states"][2]["direction"], "Right");
+        assert_eq!(json["states"][3]["loc"]["col"], 2);
+    }
+}